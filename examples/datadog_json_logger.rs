@@ -9,6 +9,8 @@ async fn main() {
             .with_env(Environment::Dev)
             .with_country(Country::Common)
             .with_version("1.0".to_string())
+            // JSON logs are replaced by human readable ones in the `Dev` environment by default
+            .with_pretty_output(false)
             // We need a tracer if we want trace and span IDs to be created and propagated, otherwise logs won't contain these correlation IDs
             // You can also setup custom tracer and custom subscriber if you don't wanna use the `traces` feature
             .with_telemetry("http://localhost:55681".to_string(), service_name)
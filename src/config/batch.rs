@@ -0,0 +1,90 @@
+use std::time::Duration;
+
+use super::EnvVarError;
+
+/// Settings of the batch span processor exporting traces to the collector.
+/// Unset values are read from the standard `OTEL_BSP_*` environment variables,
+/// falling back to the OpenTelemetry defaults.
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct BatchConfig {
+    /// Maximum number of spans buffered before being exported, spans are dropped when it's full.
+    /// See `OTEL_BSP_MAX_QUEUE_SIZE`, defaults to 2048.
+    pub max_queue_size: Option<usize>,
+    /// Maximum number of spans exported in a single batch.
+    /// See `OTEL_BSP_MAX_EXPORT_BATCH_SIZE`, defaults to 512.
+    pub max_export_batch_size: Option<usize>,
+    /// Delay between two consecutive exports.
+    /// See `OTEL_BSP_SCHEDULE_DELAY`, defaults to 5 seconds.
+    pub scheduled_delay: Option<Duration>,
+    /// Maximum duration of the export of a batch, enforced as timeout of the exporter requests
+    /// unless [`crate::TelemetryConfig::timeout`] is set.
+    /// See `OTEL_BSP_EXPORT_TIMEOUT`, defaults to the timeout of the exporter requests
+    /// (`OTEL_EXPORTER_OTLP_TIMEOUT` or 10 seconds).
+    pub export_timeout: Option<Duration>,
+}
+
+impl BatchConfig {
+    pub fn with_max_queue_size(mut self, max_queue_size: usize) -> Self {
+        self.max_queue_size = Some(max_queue_size);
+        self
+    }
+
+    pub fn with_max_export_batch_size(mut self, max_export_batch_size: usize) -> Self {
+        self.max_export_batch_size = Some(max_export_batch_size);
+        self
+    }
+
+    pub fn with_scheduled_delay(mut self, scheduled_delay: Duration) -> Self {
+        self.scheduled_delay = Some(scheduled_delay);
+        self
+    }
+
+    pub fn with_export_timeout(mut self, export_timeout: Duration) -> Self {
+        self.export_timeout = Some(export_timeout);
+        self
+    }
+
+    /// Read the `OTEL_BSP_*` environment variables using `lookup`,
+    /// returning the errors of the invalid ones alongside the valid values.
+    pub(crate) fn from_otel_env(
+        lookup: impl Fn(&str) -> Option<String>,
+    ) -> (Self, Vec<EnvVarError>) {
+        let mut errors = Vec::new();
+        let mut parse = |name: &'static str| {
+            let value = lookup(name)?;
+            match value.trim().parse::<u64>() {
+                Ok(parsed) => Some(parsed),
+                Err(_) => {
+                    errors.push(EnvVarError::Invalid {
+                        name,
+                        value,
+                        reason: "must be a non negative integer".to_string(),
+                    });
+                    None
+                }
+            }
+        };
+
+        let config = Self {
+            max_queue_size: parse("OTEL_BSP_MAX_QUEUE_SIZE").map(|v| v as usize),
+            max_export_batch_size: parse("OTEL_BSP_MAX_EXPORT_BATCH_SIZE").map(|v| v as usize),
+            scheduled_delay: parse("OTEL_BSP_SCHEDULE_DELAY").map(Duration::from_millis),
+            export_timeout: parse("OTEL_BSP_EXPORT_TIMEOUT").map(Duration::from_millis),
+        };
+
+        (config, errors)
+    }
+
+    /// Fill the unset values with the ones of `fallback`.
+    #[cfg(feature = "traces")]
+    pub(crate) fn or(self, fallback: Self) -> Self {
+        Self {
+            max_queue_size: self.max_queue_size.or(fallback.max_queue_size),
+            max_export_batch_size: self
+                .max_export_batch_size
+                .or(fallback.max_export_batch_size),
+            scheduled_delay: self.scheduled_delay.or(fallback.scheduled_delay),
+            export_timeout: self.export_timeout.or(fallback.export_timeout),
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use super::Environment;
+
+/// Default name of the environment variable holding the filter directives.
+const RUST_LOG: &str = "RUST_LOG";
+
+/// Settings of the filters deciding which spans and events are recorded.
+///
+/// The global filter, read from the environment variable, applies to every layer. The output,
+/// export and error directives can only further restrict what each layer records, so the global
+/// filter must enable everything the layers need, eg. `debug` to export debug spans while
+/// writing only `info` logs to stdout.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct FilterConfig {
+    /// Environment variable holding the filter directives, `RUST_LOG` by default.
+    pub env_var: String,
+    /// Directives used when the environment variable is not set, defaulting to
+    /// [`FilterConfig::default_directives_for`] the application environment.
+    pub default_directives: Option<String>,
+    /// Directives restricting the logs written to stdout.
+    pub output_directives: Option<String>,
+    /// Directives restricting the spans (and logs, with the `logs` feature) exported to the
+    /// collector.
+    pub export_directives: Option<String>,
+    /// Directives restricting the errors recorded on spans by [`crate::layer::ErrorLayer`].
+    pub error_directives: Option<String>,
+}
+
+impl Default for FilterConfig {
+    fn default() -> Self {
+        Self {
+            env_var: RUST_LOG.to_string(),
+            default_directives: None,
+            output_directives: None,
+            export_directives: None,
+            error_directives: None,
+        }
+    }
+}
+
+impl FilterConfig {
+    /// Built-in directives for the given [`Environment`]:
+    /// `debug` in `Dev` and `info` in `Staging` and `Production`.
+    /// The HTTP and gRPC clients used by the exporter are kept at `info` in `Dev`, as their debug
+    /// output would drown the application one.
+    pub fn default_directives_for(env: Environment) -> &'static str {
+        match env {
+            Environment::Dev => {
+                "debug,h2=info,hyper=info,hyper_util=info,reqwest=info,tonic=info,tower=info"
+            }
+            Environment::Staging | Environment::Production => "info",
+        }
+    }
+}
@@ -0,0 +1,296 @@
+use std::{
+    error::Error,
+    fmt::{Display, Formatter},
+    time::Duration,
+};
+
+use super::{
+    BatchConfig, Country, Environment, Sampling, TelemetryConfig, TelemetryProtocol, TlsConfig,
+};
+
+/// Name of the application, used as service name.
+const APP_NAME: &str = "APP_NAME";
+/// Version of the application.
+const APP_VERSION: &str = "APP_VERSION";
+/// Environment in which the application is running, see [`Environment`].
+const APP_ENV: &str = "APP_ENV";
+/// Country in which the application is running, see [`Country`].
+const COUNTRY: &str = "COUNTRY";
+/// Standard OpenTelemetry variable holding the service name reported in traces.
+const OTEL_SERVICE_NAME: &str = "OTEL_SERVICE_NAME";
+/// Standard OpenTelemetry variable holding the collector URL.
+const OTEL_EXPORTER_OTLP_ENDPOINT: &str = "OTEL_EXPORTER_OTLP_ENDPOINT";
+/// Standard OpenTelemetry variable holding the exporter protocol, see [`TelemetryProtocol`].
+const OTEL_EXPORTER_OTLP_PROTOCOL: &str = "OTEL_EXPORTER_OTLP_PROTOCOL";
+/// Standard OpenTelemetry variable holding comma separated `key=value` exporter headers.
+const OTEL_EXPORTER_OTLP_HEADERS: &str = "OTEL_EXPORTER_OTLP_HEADERS";
+/// Standard OpenTelemetry variable holding the exporter requests timeout in milliseconds.
+const OTEL_EXPORTER_OTLP_TIMEOUT: &str = "OTEL_EXPORTER_OTLP_TIMEOUT";
+/// Standard OpenTelemetry variable holding the exporter compression, `gzip` or `none`.
+const OTEL_EXPORTER_OTLP_COMPRESSION: &str = "OTEL_EXPORTER_OTLP_COMPRESSION";
+/// Standard OpenTelemetry variable holding the path of the CA bundle verifying the collector.
+const OTEL_EXPORTER_OTLP_CERTIFICATE: &str = "OTEL_EXPORTER_OTLP_CERTIFICATE";
+/// Standard OpenTelemetry variable holding the path of the client certificate for mutual TLS.
+const OTEL_EXPORTER_OTLP_CLIENT_CERTIFICATE: &str = "OTEL_EXPORTER_OTLP_CLIENT_CERTIFICATE";
+/// Standard OpenTelemetry variable holding the path of the client key for mutual TLS.
+const OTEL_EXPORTER_OTLP_CLIENT_KEY: &str = "OTEL_EXPORTER_OTLP_CLIENT_KEY";
+/// Standard OpenTelemetry variable holding the traces sampler, see [`Sampling`].
+const OTEL_TRACES_SAMPLER: &str = "OTEL_TRACES_SAMPLER";
+/// Standard OpenTelemetry variable holding the argument of the traces sampler.
+const OTEL_TRACES_SAMPLER_ARG: &str = "OTEL_TRACES_SAMPLER_ARG";
+/// Standard OpenTelemetry variable holding comma separated `key=value` resource attributes.
+const OTEL_RESOURCE_ATTRIBUTES: &str = "OTEL_RESOURCE_ATTRIBUTES";
+
+/// Values read from the environment, validated all at once.
+pub(super) struct EnvConfig {
+    pub(super) service: String,
+    pub(super) version: Option<String>,
+    pub(super) country: Country,
+    pub(super) env: Environment,
+    pub(super) telemetry: Option<TelemetryConfig>,
+    pub(super) sampling: Option<Sampling>,
+}
+
+impl EnvConfig {
+    /// Read the configuration using `lookup` to fetch variables.
+    /// Empty variables are considered as not set.
+    pub(super) fn read(lookup: impl Fn(&str) -> Option<String>) -> Result<Self, FromEnvError> {
+        let lookup = |name: &str| lookup(name).filter(|value| !value.trim().is_empty());
+        let mut errors = Vec::new();
+
+        let attributes = match lookup(OTEL_RESOURCE_ATTRIBUTES) {
+            Some(value) => parse_key_value_pairs(&value).unwrap_or_else(|reason| {
+                errors.push(EnvVarError::Invalid {
+                    name: OTEL_RESOURCE_ATTRIBUTES,
+                    value,
+                    reason,
+                });
+                Vec::new()
+            }),
+            None => Vec::new(),
+        };
+        let attribute = |key: &str| {
+            attributes
+                .iter()
+                .find(|(k, _)| k == key)
+                .map(|(_, v)| v.clone())
+        };
+
+        let otel_service_name = lookup(OTEL_SERVICE_NAME).or_else(|| attribute("service.name"));
+
+        let service = lookup(APP_NAME).or_else(|| otel_service_name.clone());
+        if service.is_none() {
+            errors.push(EnvVarError::Missing(APP_NAME));
+        }
+
+        let version = lookup(APP_VERSION).or_else(|| attribute("service.version"));
+
+        let country = parse_required(lookup, COUNTRY, &mut errors);
+        let env = parse_required(lookup, APP_ENV, &mut errors);
+        let protocol = match lookup(OTEL_EXPORTER_OTLP_PROTOCOL) {
+            Some(_) => parse_required(lookup, OTEL_EXPORTER_OTLP_PROTOCOL, &mut errors),
+            None => Some(TelemetryProtocol::default()),
+        };
+
+        let sampling = lookup(OTEL_TRACES_SAMPLER).and_then(|sampler| {
+            let arg = lookup(OTEL_TRACES_SAMPLER_ARG);
+            Sampling::from_otel_env(&sampler, arg.as_deref())
+                .map_err(|err| errors.push(err))
+                .ok()
+        });
+
+        let headers = match lookup(OTEL_EXPORTER_OTLP_HEADERS) {
+            Some(value) => parse_key_value_pairs(&value).unwrap_or_else(|_| {
+                // Headers usually hold credentials, which must not end up in the error
+                errors.push(EnvVarError::Invalid {
+                    name: OTEL_EXPORTER_OTLP_HEADERS,
+                    value: "<redacted>".to_string(),
+                    reason: "must be comma separated key=value pairs".to_string(),
+                });
+                Vec::new()
+            }),
+            None => Vec::new(),
+        };
+        let timeout = otlp_timeout(lookup).unwrap_or_else(|err| {
+            errors.push(err);
+            None
+        });
+        let gzip_compression = match lookup(OTEL_EXPORTER_OTLP_COMPRESSION).as_deref() {
+            Some("gzip") => true,
+            Some("none") | None => false,
+            Some(value) => {
+                errors.push(EnvVarError::Invalid {
+                    name: OTEL_EXPORTER_OTLP_COMPRESSION,
+                    value: value.to_string(),
+                    reason: "allowed values are 'gzip' and 'none'".to_string(),
+                });
+                false
+            }
+        };
+
+        let tls = match (
+            lookup(OTEL_EXPORTER_OTLP_CERTIFICATE),
+            lookup(OTEL_EXPORTER_OTLP_CLIENT_CERTIFICATE),
+            lookup(OTEL_EXPORTER_OTLP_CLIENT_KEY),
+        ) {
+            (None, None, None) => None,
+            (ca_certificate, client_certificate, client_key) => Some(TlsConfig {
+                ca_certificate: ca_certificate.map(Into::into),
+                client_certificate: client_certificate.map(Into::into),
+                client_key: client_key.map(Into::into),
+                insecure: false,
+            }),
+        };
+
+        let (batch, batch_errors) = BatchConfig::from_otel_env(lookup);
+        errors.extend(batch_errors);
+
+        match (service, country, env, protocol) {
+            (Some(service), Some(country), Some(env), Some(protocol)) if errors.is_empty() => {
+                let telemetry = lookup(OTEL_EXPORTER_OTLP_ENDPOINT).map(|collector_url| {
+                    let mut telemetry = TelemetryConfig::new(
+                        collector_url,
+                        otel_service_name.unwrap_or_else(|| service.clone()),
+                    )
+                    .with_protocol(protocol)
+                    .with_batch_config(batch);
+                    telemetry.headers.extend(headers);
+                    telemetry.timeout = timeout;
+                    telemetry.gzip_compression = gzip_compression;
+                    telemetry.tls = tls;
+                    telemetry
+                });
+
+                Ok(Self {
+                    service,
+                    version,
+                    country,
+                    env,
+                    telemetry,
+                    sampling,
+                })
+            }
+            _ => Err(FromEnvError { errors }),
+        }
+    }
+}
+
+fn parse_required<T>(
+    lookup: impl Fn(&str) -> Option<String>,
+    name: &'static str,
+    errors: &mut Vec<EnvVarError>,
+) -> Option<T>
+where
+    T: std::str::FromStr,
+    T::Err: Display,
+{
+    let Some(value) = lookup(name) else {
+        errors.push(EnvVarError::Missing(name));
+        return None;
+    };
+
+    match value.parse() {
+        Ok(parsed) => Some(parsed),
+        Err(err) => {
+            errors.push(EnvVarError::Invalid {
+                name,
+                reason: err.to_string(),
+                value,
+            });
+            None
+        }
+    }
+}
+
+/// Read the `OTEL_EXPORTER_OTLP_TIMEOUT` of the export requests using `lookup`.
+pub(crate) fn otlp_timeout(
+    lookup: impl Fn(&str) -> Option<String>,
+) -> Result<Option<Duration>, EnvVarError> {
+    let Some(value) = lookup(OTEL_EXPORTER_OTLP_TIMEOUT) else {
+        return Ok(None);
+    };
+
+    match value.trim().parse() {
+        Ok(millis) => Ok(Some(Duration::from_millis(millis))),
+        Err(_) => Err(EnvVarError::Invalid {
+            name: OTEL_EXPORTER_OTLP_TIMEOUT,
+            value,
+            reason: "must be a non negative integer".to_string(),
+        }),
+    }
+}
+
+/// Parse the `OTEL_RESOURCE_ATTRIBUTES` and `OTEL_EXPORTER_OTLP_HEADERS` format:
+/// `key1=value1,key2=value2`.
+fn parse_key_value_pairs(value: &str) -> Result<Vec<(String, String)>, String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| match pair.split_once('=') {
+            Some((key, value)) if !key.trim().is_empty() => {
+                Ok((key.trim().to_string(), value.trim().to_string()))
+            }
+            _ => Err(format!("'{pair}' is not a valid key=value pair")),
+        })
+        .collect()
+}
+
+/// A single environment variable that could not be read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EnvVarError {
+    /// The variable is not set or empty.
+    Missing(&'static str),
+    /// The variable is set but its value is not valid.
+    Invalid {
+        name: &'static str,
+        value: String,
+        reason: String,
+    },
+}
+
+impl Display for EnvVarError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Missing(name) => write!(f, "{name} is not set"),
+            Self::Invalid {
+                name,
+                value,
+                reason,
+            } => write!(f, "{name} has an invalid value '{value}': {reason}"),
+        }
+    }
+}
+
+/// Error returned when building a [`crate::SubscriberConfig`] from the environment.
+/// It contains every missing or invalid variable, not just the first one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FromEnvError {
+    errors: Vec<EnvVarError>,
+}
+
+impl FromEnvError {
+    #[cfg(feature = "traces")]
+    pub(crate) fn new(errors: Vec<EnvVarError>) -> Self {
+        Self { errors }
+    }
+
+    pub fn errors(&self) -> &[EnvVarError] {
+        &self.errors
+    }
+}
+
+impl Error for FromEnvError {}
+
+impl Display for FromEnvError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Invalid subscriber configuration from environment: ")?;
+        for (i, error) in self.errors.iter().enumerate() {
+            if i > 0 {
+                f.write_str("; ")?;
+            }
+            write!(f, "{error}")?;
+        }
+        Ok(())
+    }
+}
@@ -1,28 +1,91 @@
+#[cfg(feature = "traces")]
+pub(crate) use self::from_env::otlp_timeout;
 pub use self::{
+    batch::BatchConfig,
     country::Country,
     environment::{Environment, EnvironmentParseError},
+    filter::FilterConfig,
+    from_env::{EnvVarError, FromEnvError},
+    non_blocking::{NonBlockingConfig, OverflowPolicy},
+    redaction::{RedactionConfig, RedactionPattern},
+    rolling_file::{RollingFileConfig, Rotation},
+    sampling::Sampling,
+    telemetry::{TelemetryConfig, TelemetryProtocol, TelemetryProtocolParseError},
+    tls::TlsConfig,
 };
 #[cfg(feature = "json-logger")]
 use crate::json::formatter::DefaultEventFormatter;
+use tracing::Level;
+use tracing_subscriber::fmt::{writer::BoxMakeWriter, MakeWriter};
 
+mod batch;
 mod country;
 mod environment;
+mod filter;
+mod from_env;
+mod non_blocking;
+mod redaction;
+mod rolling_file;
+mod sampling;
+mod telemetry;
+mod tls;
 
 #[cfg(not(feature = "json-logger"))]
 use crate::subscriber::NopEventFormatter;
 
+#[cfg(not(feature = "json-logger"))]
+type DefaultFormatter = NopEventFormatter;
+#[cfg(feature = "json-logger")]
+type DefaultFormatter = DefaultEventFormatter;
+
 /// `SubscriberConfig` configuration built via [`SubscriberConfigBuilder`]
 /// It contains
 /// - Application env
 /// - Telemetry config
+/// - Trace sampling
+/// - Filter settings
+/// - Log writer
 /// - JSON formatter
+/// - Redaction of sensitive data
 pub struct SubscriberConfig<T> {
     pub country: Country,
     pub env: Environment,
     pub telemetry: Option<TelemetryConfig>,
+    /// Sampling of the exported traces, when `None` it's read from `OTEL_TRACES_SAMPLER`
+    /// if set or defaults to [`Sampling::for_environment`]
+    pub sampling: Option<Sampling>,
+    pub filter: FilterConfig,
+    /// Destination of the logs, stdout by default
+    pub writer: BoxMakeWriter,
+    /// Write the logs to a rotated file, instead of or alongside `writer`
+    pub file_output: Option<RollingFileConfig>,
+    /// Write the logs from a background thread, see [`crate::NonBlockingWriter`]
+    pub non_blocking: Option<NonBlockingConfig>,
     pub service: String,
     pub version: Option<String>,
     pub json_formatter: T,
+    /// Format the logs with [`crate::json::pretty::PrettyEventFormatter`] instead of
+    /// `json_formatter` when the `json-logger` feature is enabled. When `None`, only in
+    /// [`Environment::Dev`] and if no custom JSON formatter is set
+    pub pretty_output: Option<bool>,
+    /// Colorize the human readable logs with ANSI escape codes. When `None`, only if they are
+    /// written to stdout and it's a terminal
+    pub ansi: Option<bool>,
+    /// Write a line when the spans up to this level close, with the `json-logger` feature
+    pub span_close_level: Option<Level>,
+    /// Propagate the span fields to the child spans, see
+    /// [`crate::json::storage::PrimaJsonStorage::with_field_propagation`]
+    pub field_propagation: bool,
+    /// Redact sensitive data from the JSON logs and the exported spans
+    pub redaction: Option<RedactionConfig>,
+}
+
+impl SubscriberConfig<DefaultFormatter> {
+    /// Build a [`SubscriberConfig`] reading every value from environment variables.
+    /// See [`SubscriberConfigBuilder::from_env`] for the list of variables.
+    pub fn from_env() -> Result<Self, FromEnvError> {
+        SubscriberConfigBuilder::from_env().map(SubscriberConfigBuilder::build)
+    }
 }
 
 #[cfg(not(feature = "json-logger"))]
@@ -43,11 +106,6 @@ pub fn builder(
     )
 }
 
-pub struct TelemetryConfig {
-    pub collector_url: String,
-    pub service_name: String,
-}
-
 pub struct WithoutCountry;
 pub struct WithCountry(Country);
 
@@ -58,9 +116,20 @@ pub struct SubscriberConfigBuilder<F, C, E> {
     country: C,
     env: E,
     telemetry: Option<TelemetryConfig>,
+    batch: Option<BatchConfig>,
+    sampling: Option<Sampling>,
+    filter: FilterConfig,
+    writer: BoxMakeWriter,
+    file_output: Option<RollingFileConfig>,
+    non_blocking: Option<NonBlockingConfig>,
     service: String,
     version: Option<String>,
     formatter: F,
+    pretty_output: Option<bool>,
+    ansi: Option<bool>,
+    span_close_level: Option<Level>,
+    field_propagation: bool,
+    redaction: Option<RedactionConfig>,
 }
 
 impl<F, C, E> SubscriberConfigBuilder<F, C, E> {
@@ -71,24 +140,149 @@ impl<F, C, E> SubscriberConfigBuilder<F, C, E> {
     }
 
     /// Set the telemetry collector URL and the service name included in telemetry traces.
-    pub fn with_telemetry(mut self, collector_url: String, service_name: String) -> Self {
-        self.telemetry = Some(TelemetryConfig {
-            collector_url,
-            service_name,
-        });
+    pub fn with_telemetry(self, collector_url: String, service_name: String) -> Self {
+        self.with_telemetry_config(TelemetryConfig::new(collector_url, service_name))
+    }
+
+    /// Set the telemetry configuration, allowing to customise the exporter.
+    pub fn with_telemetry_config(mut self, telemetry: TelemetryConfig) -> Self {
+        self.telemetry = Some(telemetry);
+        self
+    }
+
+    /// Set the settings of the batch span processor, replacing the ones of the
+    /// [`TelemetryConfig`]. They have no effect if telemetry is not configured.
+    pub fn with_batch_config(mut self, batch: BatchConfig) -> Self {
+        self.batch = Some(batch);
+        self
+    }
+
+    /// Set the sampling of the exported traces, overriding `OTEL_TRACES_SAMPLER` and the
+    /// [`Environment`] default. A ratio not between `0.0` and `1.0` makes configuring the
+    /// subscriber fail with [`crate::Error::InvalidSamplingRatio`].
+    pub fn with_sampling(mut self, sampling: Sampling) -> Self {
+        self.sampling = Some(sampling);
+        self
+    }
+
+    /// Set the filter directives used when the filter environment variable is not set,
+    /// overriding the [`Environment`] default (see [`FilterConfig::default_directives_for`]).
+    pub fn with_default_directives(mut self, directives: String) -> Self {
+        self.filter.default_directives = Some(directives);
+        self
+    }
+
+    /// Restrict the logs written to stdout, on top of the global filter.
+    pub fn with_output_directives(mut self, directives: String) -> Self {
+        self.filter.output_directives = Some(directives);
+        self
+    }
+
+    /// Restrict the spans and logs exported to the collector, on top of the global filter.
+    pub fn with_export_directives(mut self, directives: String) -> Self {
+        self.filter.export_directives = Some(directives);
+        self
+    }
+
+    /// Restrict the errors recorded on spans by the `ErrorLayer`, on top of the global filter.
+    pub fn with_error_directives(mut self, directives: String) -> Self {
+        self.filter.error_directives = Some(directives);
+        self
+    }
 
+    /// Set the name of the environment variable holding the filter directives,
+    /// `RUST_LOG` by default.
+    pub fn with_filter_env_var(mut self, env_var: String) -> Self {
+        self.filter.env_var = env_var;
+        self
+    }
+
+    /// Write the logs to the writers returned by `make_writer` instead of stdout,
+    /// eg. [`std::io::stderr`], a file or an in-memory buffer.
+    /// The human readable logs are not colorized, unless requested with [`Self::with_ansi`].
+    pub fn with_writer<W>(mut self, make_writer: W) -> Self
+    where
+        W: for<'writer> MakeWriter<'writer> + Send + Sync + 'static,
+    {
+        self.writer = BoxMakeWriter::new(make_writer);
+        self.ansi = self.ansi.or(Some(false));
+        self
+    }
+
+    /// Write the logs to a file rotated by size or time instead of stdout, or alongside it
+    /// with [`RollingFileConfig::with_stdout`].
+    pub fn with_file_output(mut self, config: RollingFileConfig) -> Self {
+        self.file_output = Some(config);
+        self.ansi = self.ansi.or(Some(false));
+        self
+    }
+
+    /// Write the logs from a background thread instead of the one emitting the events,
+    /// see [`crate::NonBlockingWriter`].
+    pub fn with_non_blocking(mut self, config: NonBlockingConfig) -> Self {
+        self.non_blocking = Some(config);
+        self
+    }
+
+    /// Choose between the human readable logs and the JSON formatter when the `json-logger`
+    /// feature is enabled, by default the human readable logs are used only in
+    /// [`Environment::Dev`].
+    pub fn with_pretty_output(mut self, pretty_output: bool) -> Self {
+        self.pretty_output = Some(pretty_output);
+        self
+    }
+
+    /// Choose whether to colorize the human readable logs with ANSI escape codes, by default only
+    /// when they are written to stdout and it's a terminal. Useful eg. for [`std::io::stderr`].
+    pub fn with_ansi(mut self, ansi: bool) -> Self {
+        self.ansi = Some(ansi);
+        self
+    }
+
+    /// Write a line with the elapsed, busy and idle time of the spans up to `level` when they
+    /// close, with the `json-logger` feature. Useful to analyse the latency from the logs when
+    /// the traces are sampled out.
+    pub fn with_span_close_events(mut self, level: Level) -> Self {
+        self.span_close_level = Some(level);
+        self
+    }
+
+    /// Propagate the fields of the spans to their children with the `json-logger` feature, so
+    /// that the formatters writing the span fields at root level read them from the current span,
+    /// see [`crate::json::storage::PrimaJsonStorage::with_field_propagation`].
+    pub fn with_field_propagation(mut self) -> Self {
+        self.field_propagation = true;
+        self
+    }
+
+    /// Redact the denied fields and the values matching the patterns of `config` from the JSON
+    /// logs and the exported spans and logs. Requires the `json-logger` feature.
+    pub fn with_redaction(mut self, config: RedactionConfig) -> Self {
+        self.redaction = Some(config);
         self
     }
 
     /// Set the custom JSON formatter to be used when the feature `json-logger` is activated.
+    /// Unless requested with [`Self::with_pretty_output`], it's used in every environment.
     pub fn with_custom_json_formatter<G>(self, formatter: G) -> SubscriberConfigBuilder<G, C, E> {
         SubscriberConfigBuilder {
             formatter,
+            pretty_output: self.pretty_output.or(Some(false)),
+            ansi: self.ansi,
             country: self.country,
             env: self.env,
             service: self.service,
             version: self.version,
             telemetry: self.telemetry,
+            batch: self.batch,
+            sampling: self.sampling,
+            filter: self.filter,
+            writer: self.writer,
+            file_output: self.file_output,
+            non_blocking: self.non_blocking,
+            span_close_level: self.span_close_level,
+            field_propagation: self.field_propagation,
+            redaction: self.redaction,
         }
     }
 }
@@ -107,7 +301,7 @@ impl<F> SubscriberConfigBuilder<F, WithoutCountry, WithoutEnvironment> {
     pub fn new(
         service: &str,
     ) -> SubscriberConfigBuilder<DefaultEventFormatter, WithoutCountry, WithoutEnvironment> {
-        Self::_new(service, DefaultEventFormatter)
+        Self::_new(service, DefaultEventFormatter::default())
     }
 
     fn _new<G>(
@@ -119,8 +313,19 @@ impl<F> SubscriberConfigBuilder<F, WithoutCountry, WithoutEnvironment> {
             country: WithoutCountry,
             env: WithoutEnvironment,
             telemetry: None,
+            batch: None,
+            sampling: None,
+            filter: FilterConfig::default(),
+            writer: BoxMakeWriter::new(std::io::stdout),
+            file_output: None,
+            non_blocking: None,
             version: None,
             formatter,
+            pretty_output: None,
+            ansi: None,
+            span_close_level: None,
+            field_propagation: false,
+            redaction: None,
         }
     }
 }
@@ -132,9 +337,20 @@ impl<F, E> SubscriberConfigBuilder<F, WithoutCountry, E> {
             country: WithCountry(country),
             env: self.env,
             telemetry: self.telemetry,
+            batch: self.batch,
+            sampling: self.sampling,
+            filter: self.filter,
+            writer: self.writer,
+            file_output: self.file_output,
+            non_blocking: self.non_blocking,
             service: self.service,
             version: self.version,
             formatter: self.formatter,
+            pretty_output: self.pretty_output,
+            ansi: self.ansi,
+            span_close_level: self.span_close_level,
+            field_propagation: self.field_propagation,
+            redaction: self.redaction,
         }
     }
 }
@@ -146,23 +362,500 @@ impl<F, C> SubscriberConfigBuilder<F, C, WithoutEnvironment> {
             country: self.country,
             env: WithEnvironment(env),
             telemetry: self.telemetry,
+            batch: self.batch,
+            sampling: self.sampling,
+            filter: self.filter,
+            writer: self.writer,
+            file_output: self.file_output,
+            non_blocking: self.non_blocking,
             service: self.service,
             version: self.version,
             formatter: self.formatter,
+            pretty_output: self.pretty_output,
+            ansi: self.ansi,
+            span_close_level: self.span_close_level,
+            field_propagation: self.field_propagation,
+            redaction: self.redaction,
         }
     }
 }
 
+impl SubscriberConfigBuilder<DefaultFormatter, WithCountry, WithEnvironment> {
+    /// Create a [`SubscriberConfigBuilder`] reading its values from environment variables:
+    /// - `APP_NAME`: service name, falls back to `OTEL_SERVICE_NAME` and then to the
+    ///   `service.name` attribute in `OTEL_RESOURCE_ATTRIBUTES`
+    /// - `APP_VERSION`: optional version, falls back to the `service.version` attribute in
+    ///   `OTEL_RESOURCE_ATTRIBUTES`
+    /// - `COUNTRY`: the [`Country`]
+    /// - `APP_ENV`: the [`Environment`]
+    /// - `OTEL_EXPORTER_OTLP_ENDPOINT`: optional collector URL, telemetry is configured only if set
+    /// - `OTEL_SERVICE_NAME`: optional service name included in telemetry traces, defaults to the
+    ///   service name
+    /// - `OTEL_EXPORTER_OTLP_PROTOCOL`: optional [`TelemetryProtocol`], defaults to `http/protobuf`
+    /// - `OTEL_TRACES_SAMPLER` and `OTEL_TRACES_SAMPLER_ARG`: optional [`Sampling`], defaults to
+    ///   [`Sampling::for_environment`]
+    /// - `OTEL_EXPORTER_OTLP_HEADERS`, `OTEL_EXPORTER_OTLP_TIMEOUT` and
+    ///   `OTEL_EXPORTER_OTLP_COMPRESSION`: optional exporter settings, see [`TelemetryConfig`]
+    /// - `OTEL_EXPORTER_OTLP_CERTIFICATE`, `OTEL_EXPORTER_OTLP_CLIENT_CERTIFICATE` and
+    ///   `OTEL_EXPORTER_OTLP_CLIENT_KEY`: optional [`TlsConfig`] file paths
+    /// - `OTEL_BSP_*`: optional [`BatchConfig`] settings
+    ///
+    /// Every missing or invalid variable is reported in the returned [`FromEnvError`].
+    /// The builder can still be customised afterwards.
+    pub fn from_env() -> Result<Self, FromEnvError> {
+        Self::from_lookup(|name| std::env::var(name).ok())
+    }
+
+    fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Result<Self, FromEnvError> {
+        let config = from_env::EnvConfig::read(lookup)?;
+
+        Ok(SubscriberConfigBuilder {
+            country: WithCountry(config.country),
+            env: WithEnvironment(config.env),
+            telemetry: config.telemetry,
+            batch: None,
+            sampling: config.sampling,
+            filter: FilterConfig::default(),
+            writer: BoxMakeWriter::new(std::io::stdout),
+            file_output: None,
+            non_blocking: None,
+            service: config.service,
+            version: config.version,
+            formatter: DefaultFormatter::default(),
+            pretty_output: None,
+            ansi: None,
+            span_close_level: None,
+            field_propagation: false,
+            redaction: None,
+        })
+    }
+}
+
 impl<F> SubscriberConfigBuilder<F, WithCountry, WithEnvironment> {
     /// Build a [`SubscriberConfig`]
     pub fn build(self) -> SubscriberConfig<F> {
+        let telemetry = match (self.telemetry, self.batch) {
+            (Some(telemetry), Some(batch)) => Some(telemetry.with_batch_config(batch)),
+            (telemetry, _) => telemetry,
+        };
+
         SubscriberConfig {
             country: self.country.0,
             env: self.env.0,
-            telemetry: self.telemetry,
+            telemetry,
+            sampling: self.sampling,
+            filter: self.filter,
+            writer: self.writer,
+            file_output: self.file_output,
+            non_blocking: self.non_blocking,
             service: self.service,
             version: self.version,
             json_formatter: self.formatter,
+            pretty_output: self.pretty_output,
+            ansi: self.ansi,
+            span_close_level: self.span_close_level,
+            field_propagation: self.field_propagation,
+            redaction: self.redaction,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn lookup(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        move |name| vars.get(name).cloned()
+    }
+
+    #[test]
+    fn from_env_reads_all_variables() {
+        let config = SubscriberConfigBuilder::from_lookup(lookup(&[
+            ("APP_NAME", "my-app"),
+            ("APP_VERSION", "1.2.3"),
+            ("COUNTRY", "it"),
+            ("APP_ENV", "staging"),
+            ("OTEL_EXPORTER_OTLP_ENDPOINT", "http://collector:4318"),
+            ("OTEL_SERVICE_NAME", "my-app-traces"),
+            ("OTEL_EXPORTER_OTLP_PROTOCOL", "http/json"),
+        ]))
+        .unwrap()
+        .build();
+
+        assert_eq!(config.service, "my-app");
+        assert_eq!(config.version.as_deref(), Some("1.2.3"));
+        assert_eq!(config.country, Country::It);
+        assert_eq!(config.env, Environment::Staging);
+
+        let telemetry = config.telemetry.unwrap();
+        assert_eq!(telemetry.collector_url, "http://collector:4318");
+        assert_eq!(telemetry.service_name, "my-app-traces");
+        assert_eq!(telemetry.protocol, TelemetryProtocol::HttpJson);
+        assert!(config.sampling.is_none());
+    }
+
+    #[test]
+    fn from_env_reads_sampling() {
+        let config = SubscriberConfigBuilder::from_lookup(lookup(&[
+            ("APP_NAME", "my-app"),
+            ("COUNTRY", "it"),
+            ("APP_ENV", "production"),
+            ("OTEL_TRACES_SAMPLER", "parentbased_traceidratio"),
+            ("OTEL_TRACES_SAMPLER_ARG", "0.25"),
+        ]))
+        .unwrap()
+        .build();
+
+        assert_eq!(
+            config.sampling,
+            Some(Sampling::ParentBased(Box::new(Sampling::TraceIdRatio(
+                0.25
+            ))))
+        );
+    }
+
+    #[test]
+    fn sampling_from_otel_env() {
+        assert_eq!(
+            Sampling::from_otel_env("always_off", None),
+            Ok(Sampling::AlwaysOff)
+        );
+        assert_eq!(
+            Sampling::from_otel_env("traceidratio", None),
+            Ok(Sampling::TraceIdRatio(1.0))
+        );
+        assert!(matches!(
+            Sampling::from_otel_env("traceidratio", Some("2")),
+            Err(EnvVarError::Invalid {
+                name: "OTEL_TRACES_SAMPLER_ARG",
+                ..
+            })
+        ));
+        assert!(matches!(
+            Sampling::from_otel_env("sometimes", None),
+            Err(EnvVarError::Invalid {
+                name: "OTEL_TRACES_SAMPLER",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn sampling_for_environment() {
+        assert_eq!(
+            Sampling::for_environment(Environment::Dev),
+            Sampling::ParentBased(Box::new(Sampling::AlwaysOn))
+        );
+        assert_eq!(
+            Sampling::for_environment(Environment::Production),
+            Sampling::ParentBased(Box::new(Sampling::TraceIdRatio(0.1)))
+        );
+    }
+
+    #[test]
+    fn sampling_validate() {
+        assert!(Sampling::for_environment(Environment::Production)
+            .validate()
+            .is_ok());
+        assert!(Sampling::TraceIdRatio(1.0).validate().is_ok());
+
+        for ratio in [-0.1, 1.5, f64::NAN] {
+            let sampling = Sampling::ParentBased(Box::new(Sampling::TraceIdRatio(ratio)));
+            assert!(matches!(
+                sampling.validate(),
+                Err(crate::Error::InvalidSamplingRatio(_))
+            ));
         }
     }
+
+    #[test]
+    fn from_env_falls_back_to_otel_variables() {
+        let config = SubscriberConfigBuilder::from_lookup(lookup(&[
+            ("COUNTRY", "es"),
+            ("APP_ENV", "dev"),
+            ("OTEL_EXPORTER_OTLP_ENDPOINT", "http://collector:4318"),
+            (
+                "OTEL_RESOURCE_ATTRIBUTES",
+                "service.name=from-attributes, service.version=0.1.0",
+            ),
+        ]))
+        .unwrap()
+        .build();
+
+        assert_eq!(config.service, "from-attributes");
+        assert_eq!(config.version.as_deref(), Some("0.1.0"));
+        assert_eq!(config.telemetry.unwrap().service_name, "from-attributes");
+    }
+
+    #[test]
+    fn from_env_without_endpoint_disables_telemetry() {
+        let config = SubscriberConfigBuilder::from_lookup(lookup(&[
+            ("APP_NAME", "my-app"),
+            ("COUNTRY", "uk"),
+            ("APP_ENV", "production"),
+            ("OTEL_EXPORTER_OTLP_ENDPOINT", ""),
+        ]))
+        .unwrap()
+        .build();
+
+        assert!(config.telemetry.is_none());
+        assert!(config.version.is_none());
+    }
+
+    #[test]
+    fn from_env_reports_every_error() {
+        let error = SubscriberConfigBuilder::from_lookup(lookup(&[
+            ("COUNTRY", "fr"),
+            ("OTEL_RESOURCE_ATTRIBUTES", "service.version"),
+            ("OTEL_EXPORTER_OTLP_PROTOCOL", "udp"),
+        ]))
+        .err()
+        .unwrap();
+
+        assert_eq!(error.errors().len(), 5);
+        assert!(matches!(
+            error.errors()[0],
+            EnvVarError::Invalid {
+                name: "OTEL_RESOURCE_ATTRIBUTES",
+                ..
+            }
+        ));
+        assert_eq!(error.errors()[1], EnvVarError::Missing("APP_NAME"));
+        assert!(matches!(
+            error.errors()[2],
+            EnvVarError::Invalid {
+                name: "COUNTRY",
+                ..
+            }
+        ));
+        assert_eq!(error.errors()[3], EnvVarError::Missing("APP_ENV"));
+        assert!(matches!(
+            error.errors()[4],
+            EnvVarError::Invalid {
+                name: "OTEL_EXPORTER_OTLP_PROTOCOL",
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn from_env_reads_batch_config() {
+        let config = SubscriberConfigBuilder::from_lookup(lookup(&[
+            ("APP_NAME", "my-app"),
+            ("COUNTRY", "it"),
+            ("APP_ENV", "production"),
+            ("OTEL_EXPORTER_OTLP_ENDPOINT", "http://collector:4318"),
+            ("OTEL_BSP_MAX_QUEUE_SIZE", "4096"),
+            ("OTEL_BSP_SCHEDULE_DELAY", "1000"),
+            ("OTEL_BSP_EXPORT_TIMEOUT", "2500"),
+        ]))
+        .unwrap()
+        .build();
+
+        assert_eq!(
+            config.telemetry.unwrap().batch,
+            BatchConfig::default()
+                .with_max_queue_size(4096)
+                .with_scheduled_delay(std::time::Duration::from_secs(1))
+                .with_export_timeout(std::time::Duration::from_millis(2500))
+        );
+    }
+
+    #[test]
+    fn batch_config_is_set_on_telemetry() {
+        let batch = BatchConfig::default().with_max_queue_size(4096);
+        let config = builder("my-app")
+            .with_batch_config(batch.clone())
+            .with_telemetry("http://collector:4318".to_string(), "my-app".to_string())
+            .with_country(Country::It)
+            .with_env(Environment::Production)
+            .build();
+
+        assert_eq!(config.telemetry.unwrap().batch, batch);
+    }
+
+    #[test]
+    fn from_env_reports_invalid_batch_config() {
+        let error = SubscriberConfigBuilder::from_lookup(lookup(&[
+            ("APP_NAME", "my-app"),
+            ("COUNTRY", "it"),
+            ("APP_ENV", "production"),
+            ("OTEL_BSP_MAX_EXPORT_BATCH_SIZE", "-1"),
+            ("OTEL_BSP_MAX_QUEUE_SIZE", "many"),
+        ]))
+        .err()
+        .unwrap();
+
+        assert_eq!(error.errors().len(), 2);
+        assert!(matches!(
+            error.errors()[0],
+            EnvVarError::Invalid {
+                name: "OTEL_BSP_MAX_QUEUE_SIZE",
+                ..
+            }
+        ));
+        assert!(matches!(
+            error.errors()[1],
+            EnvVarError::Invalid {
+                name: "OTEL_BSP_MAX_EXPORT_BATCH_SIZE",
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn from_env_reads_exporter_settings() {
+        let config = SubscriberConfigBuilder::from_lookup(lookup(&[
+            ("APP_NAME", "my-app"),
+            ("COUNTRY", "it"),
+            ("APP_ENV", "production"),
+            ("OTEL_EXPORTER_OTLP_ENDPOINT", "http://collector:4318"),
+            (
+                "OTEL_EXPORTER_OTLP_HEADERS",
+                "Authorization=Bearer token, x-tenant=prima",
+            ),
+            ("OTEL_EXPORTER_OTLP_TIMEOUT", "3000"),
+            ("OTEL_EXPORTER_OTLP_COMPRESSION", "gzip"),
+        ]))
+        .unwrap()
+        .build();
+
+        let telemetry = config.telemetry.unwrap();
+        assert_eq!(
+            telemetry.headers,
+            HashMap::from([
+                ("Authorization".to_string(), "Bearer token".to_string()),
+                ("x-tenant".to_string(), "prima".to_string()),
+            ])
+        );
+        assert_eq!(telemetry.timeout, Some(std::time::Duration::from_secs(3)));
+        assert!(telemetry.gzip_compression);
+    }
+
+    #[test]
+    fn from_env_redacts_invalid_headers() {
+        let error = SubscriberConfigBuilder::from_lookup(lookup(&[
+            ("APP_NAME", "my-app"),
+            ("COUNTRY", "it"),
+            ("APP_ENV", "production"),
+            ("OTEL_EXPORTER_OTLP_HEADERS", "Authorization:secret"),
+            ("OTEL_EXPORTER_OTLP_COMPRESSION", "zstd"),
+        ]))
+        .err()
+        .unwrap();
+
+        assert_eq!(error.errors().len(), 2);
+        assert!(matches!(
+            error.errors()[0],
+            EnvVarError::Invalid {
+                name: "OTEL_EXPORTER_OTLP_HEADERS",
+                ..
+            }
+        ));
+        assert!(!error.to_string().contains("secret"));
+        assert!(matches!(
+            error.errors()[1],
+            EnvVarError::Invalid {
+                name: "OTEL_EXPORTER_OTLP_COMPRESSION",
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn from_env_reads_tls_config() {
+        let config = SubscriberConfigBuilder::from_lookup(lookup(&[
+            ("APP_NAME", "my-app"),
+            ("COUNTRY", "it"),
+            ("APP_ENV", "production"),
+            ("OTEL_EXPORTER_OTLP_ENDPOINT", "https://collector:4318"),
+            ("OTEL_EXPORTER_OTLP_CERTIFICATE", "/etc/ssl/ca.pem"),
+            (
+                "OTEL_EXPORTER_OTLP_CLIENT_CERTIFICATE",
+                "/etc/ssl/client.pem",
+            ),
+            ("OTEL_EXPORTER_OTLP_CLIENT_KEY", "/etc/ssl/client.key"),
+        ]))
+        .unwrap()
+        .build();
+
+        assert_eq!(
+            config.telemetry.unwrap().tls,
+            Some(
+                TlsConfig::default()
+                    .with_ca_certificate("/etc/ssl/ca.pem")
+                    .with_client_identity("/etc/ssl/client.pem", "/etc/ssl/client.key")
+            )
+        );
+    }
+
+    #[test]
+    fn field_propagation_is_set() {
+        let config = builder("my-app")
+            .with_country(Country::It)
+            .with_env(Environment::Dev)
+            .build();
+        assert!(!config.field_propagation);
+
+        let config = builder("my-app")
+            .with_field_propagation()
+            .with_country(Country::It)
+            .with_env(Environment::Dev)
+            .build();
+        assert!(config.field_propagation);
+    }
+
+    #[test]
+    fn custom_writer_disables_ansi() {
+        let config = builder("my-app")
+            .with_country(Country::It)
+            .with_env(Environment::Dev)
+            .build();
+        assert_eq!(config.ansi, None);
+
+        let config = builder("my-app")
+            .with_writer(std::io::stderr)
+            .with_country(Country::It)
+            .with_env(Environment::Dev)
+            .build();
+        assert_eq!(config.ansi, Some(false));
+
+        let config = builder("my-app")
+            .with_ansi(true)
+            .with_writer(std::io::stderr)
+            .with_country(Country::It)
+            .with_env(Environment::Dev)
+            .build();
+        assert_eq!(config.ansi, Some(true));
+    }
+
+    #[test]
+    fn custom_json_formatter_disables_pretty_output() {
+        let config = builder("my-app")
+            .with_country(Country::It)
+            .with_env(Environment::Dev)
+            .build();
+        assert_eq!(config.pretty_output, None);
+
+        let config = builder("my-app")
+            .with_custom_json_formatter(())
+            .with_country(Country::It)
+            .with_env(Environment::Dev)
+            .build();
+        assert_eq!(config.pretty_output, Some(false));
+
+        let config = builder("my-app")
+            .with_pretty_output(true)
+            .with_custom_json_formatter(())
+            .with_country(Country::It)
+            .with_env(Environment::Dev)
+            .build();
+        assert_eq!(config.pretty_output, Some(true));
+    }
 }
@@ -0,0 +1,43 @@
+/// Default number of lines buffered by the non-blocking writer.
+const DEFAULT_BUFFERED_LINES: usize = 128_000;
+
+/// What the non-blocking writer does with a new line when its buffer is full.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub enum OverflowPolicy {
+    /// Discard the line, counting it in [`crate::NonBlockingWriter::dropped_lines`].
+    #[default]
+    Drop,
+    /// Wait for the background thread to make room for the line.
+    Block,
+}
+
+/// Settings of the non-blocking writer, which hands the log lines to a background thread
+/// instead of writing them on the thread emitting the event.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct NonBlockingConfig {
+    /// Maximum number of lines waiting to be written, 128000 by default.
+    pub buffered_lines: usize,
+    /// What to do with a new line when `buffered_lines` are already waiting.
+    pub overflow_policy: OverflowPolicy,
+}
+
+impl Default for NonBlockingConfig {
+    fn default() -> Self {
+        Self {
+            buffered_lines: DEFAULT_BUFFERED_LINES,
+            overflow_policy: OverflowPolicy::default(),
+        }
+    }
+}
+
+impl NonBlockingConfig {
+    pub fn with_buffered_lines(mut self, buffered_lines: usize) -> Self {
+        self.buffered_lines = buffered_lines;
+        self
+    }
+
+    pub fn with_overflow_policy(mut self, overflow_policy: OverflowPolicy) -> Self {
+        self.overflow_policy = overflow_policy;
+        self
+    }
+}
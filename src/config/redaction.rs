@@ -0,0 +1,121 @@
+use std::fmt::{Debug, Formatter};
+
+use crate::Country;
+
+/// Built-in patterns of sensitive values scrubbed from the logs and spans,
+/// see [`RedactionConfig::with_pattern`].
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum RedactionPattern {
+    /// Email addresses.
+    Email,
+    /// International bank account numbers, eg. `IT60X0542811101000000123456`.
+    Iban,
+    /// Bearer tokens and JWTs, eg. from `Authorization` headers.
+    AuthToken,
+    /// Italian fiscal codes (codice fiscale), eg. `RSSMRA85T10A562S`.
+    ItalianFiscalCode,
+    /// Spanish identity numbers of citizens (DNI) and foreigners (NIE), eg. `12345678Z`.
+    SpanishIdentityNumber,
+    /// UK National Insurance numbers, eg. `QQ 12 34 56 C`.
+    UkNationalInsuranceNumber,
+}
+
+impl RedactionPattern {
+    /// Patterns of the personal identifiers of `country`, none for [`Country::Common`].
+    pub fn for_country(country: Country) -> &'static [RedactionPattern] {
+        match country {
+            Country::Common => &[],
+            Country::It => &[RedactionPattern::ItalianFiscalCode],
+            Country::Es => &[RedactionPattern::SpanishIdentityNumber],
+            Country::Uk => &[RedactionPattern::UkNationalInsuranceNumber],
+        }
+    }
+
+    #[cfg(all(
+        feature = "redaction",
+        any(feature = "json-logger", feature = "traces")
+    ))]
+    pub(crate) fn regex(self) -> &'static str {
+        match self {
+            Self::Email => r"(?i)\b[a-z0-9._%+-]+@[a-z0-9-]+(?:\.[a-z0-9-]+)*\.[a-z]{2,}\b",
+            Self::Iban => r"\b[A-Z]{2}[0-9]{2}(?: ?[A-Z0-9]){11,30}\b",
+            Self::AuthToken => {
+                r"(?i:bearer)\s+[A-Za-z0-9\-._~+/]+=*|\beyJ[A-Za-z0-9_-]*\.[A-Za-z0-9_-]+\.[A-Za-z0-9_-]*"
+            }
+            Self::ItalianFiscalCode => {
+                r"(?i)\b[A-Z]{6}[0-9LMNP-V]{2}[ABCDEHLMPRST][0-9LMNP-V]{2}[A-Z][0-9LMNP-V]{3}[A-Z]\b"
+            }
+            Self::SpanishIdentityNumber => r"(?i)\b(?:[0-9]{8}|[XYZ]-?[0-9]{7})-?[A-HJ-NP-TV-Z]\b",
+            Self::UkNationalInsuranceNumber => {
+                r"(?i)\b[A-CEGHJ-PR-TW-Z][A-CEGHJ-NPR-TW-Z] ?[0-9]{2} ?[0-9]{2} ?[0-9]{2} ?[A-D]\b"
+            }
+        }
+    }
+}
+
+/// Redaction of sensitive data, requiring the `redaction` feature. It applies to the JSON logs,
+/// with the `json-logger` feature, to the attributes of the exported spans, with the `traces`
+/// feature, and to the exported logs, with the `logs` feature. The logs written without the
+/// `json-logger` feature are not redacted, and configuring redaction when it can't apply anywhere
+/// fails with [`crate::Error::RedactionNotSupported`].
+///
+/// The values of the denied fields are replaced altogether, while the parts of the other string
+/// values matching a pattern are replaced with `[REDACTED]`, or with a hash when enabled with
+/// [`RedactionConfig::with_hashing`].
+#[derive(Clone, Default)]
+pub struct RedactionConfig {
+    /// Names of the fields whose values are always redacted, either exact or globs where `*`
+    /// matches any sequence of characters, eg. `password` or `*token*`. Case insensitive.
+    pub denied_fields: Vec<String>,
+    /// Built-in patterns of sensitive values to redact, eg. emails.
+    pub patterns: Vec<RedactionPattern>,
+    /// Regular expressions, with the syntax of the `regex` crate, of other values to redact.
+    pub custom_patterns: Vec<String>,
+    /// Key of the HMAC-SHA256 hash replacing the redacted values.
+    pub hash_key: Option<Vec<u8>>,
+}
+
+impl RedactionConfig {
+    pub fn with_denied_field(mut self, field: impl Into<String>) -> Self {
+        self.denied_fields.push(field.into());
+        self
+    }
+
+    pub fn with_pattern(mut self, pattern: RedactionPattern) -> Self {
+        self.patterns.push(pattern);
+        self
+    }
+
+    /// Redact the personal identifiers of `country`, see [`RedactionPattern::for_country`].
+    pub fn with_country_patterns(mut self, country: Country) -> Self {
+        self.patterns
+            .extend_from_slice(RedactionPattern::for_country(country));
+        self
+    }
+
+    /// Redact the values matching `regex`, validated when configuring the subscriber.
+    pub fn with_custom_pattern(mut self, regex: impl Into<String>) -> Self {
+        self.custom_patterns.push(regex.into());
+        self
+    }
+
+    /// Replace the redacted values with `[REDACTED:<hash>]`, the truncated HMAC-SHA256 of the
+    /// value with `key`, so that the same value can be correlated across logs and spans without
+    /// being disclosed. The key should be kept secret, as it allows to verify guessed values.
+    pub fn with_hashing(mut self, key: impl Into<Vec<u8>>) -> Self {
+        self.hash_key = Some(key.into());
+        self
+    }
+}
+
+impl Debug for RedactionConfig {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        // The hash key is a secret
+        f.debug_struct("RedactionConfig")
+            .field("denied_fields", &self.denied_fields)
+            .field("patterns", &self.patterns)
+            .field("custom_patterns", &self.custom_patterns)
+            .field("hashing", &self.hash_key.is_some())
+            .finish()
+    }
+}
@@ -0,0 +1,51 @@
+use std::path::PathBuf;
+
+/// Default number of rotated files kept next to the current one.
+const DEFAULT_MAX_ARCHIVES: usize = 5;
+
+/// When the log file is rotated.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Rotation {
+    /// Before a line would make the file bigger than the given number of bytes.
+    Size(u64),
+    /// At the beginning of every hour, UTC.
+    Hourly,
+    /// At midnight, UTC.
+    Daily,
+}
+
+/// Settings of the file the logs are written to, see [`crate::RollingFileWriter`].
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct RollingFileConfig {
+    /// Path of the current log file, its parent directory is created if missing.
+    pub path: PathBuf,
+    pub rotation: Rotation,
+    /// Number of rotated files kept, named after `path` with a `.1`, `.2`, ... suffix from
+    /// the most recent one. 5 by default, with 0 the file is truncated when rotated.
+    pub max_archives: usize,
+    /// Keep writing the logs to stdout, or the writer set with
+    /// [`crate::SubscriberConfigBuilder::with_writer`], alongside the file.
+    pub stdout: bool,
+}
+
+impl RollingFileConfig {
+    pub fn new(path: impl Into<PathBuf>, rotation: Rotation) -> Self {
+        Self {
+            path: path.into(),
+            rotation,
+            max_archives: DEFAULT_MAX_ARCHIVES,
+            stdout: false,
+        }
+    }
+
+    pub fn with_max_archives(mut self, max_archives: usize) -> Self {
+        self.max_archives = max_archives;
+        self
+    }
+
+    /// Write the logs to stdout as well as to the file.
+    pub fn with_stdout(mut self) -> Self {
+        self.stdout = true;
+        self
+    }
+}
@@ -0,0 +1,70 @@
+use super::{EnvVarError, Environment};
+use crate::Error;
+
+/// Sampling strategy of the exported traces.
+#[derive(PartialEq, Debug, Clone)]
+pub enum Sampling {
+    /// Sample every trace
+    AlwaysOn,
+    /// Never sample traces
+    AlwaysOff,
+    /// Sample the given ratio of traces, from `0.0` (none) to `1.0` (all), based on the trace id
+    TraceIdRatio(f64),
+    /// Follow the sampling decision of the parent span if any,
+    /// otherwise use the wrapped strategy for root spans
+    ParentBased(Box<Sampling>),
+}
+
+impl Sampling {
+    /// Default sampling for the given [`Environment`]:
+    /// every trace in `Dev` and `Staging`, 10% of traces in `Production`.
+    /// The sampling decision of remote parents is always honoured.
+    pub fn for_environment(env: Environment) -> Self {
+        let root = match env {
+            Environment::Dev | Environment::Staging => Self::AlwaysOn,
+            Environment::Production => Self::TraceIdRatio(0.1),
+        };
+        Self::ParentBased(Box::new(root))
+    }
+
+    /// Check that the ratios are between `0.0` and `1.0`, as required for `OTEL_TRACES_SAMPLER_ARG`.
+    pub(crate) fn validate(&self) -> Result<(), Error> {
+        match self {
+            Self::TraceIdRatio(ratio) if !(0.0..=1.0).contains(ratio) => {
+                Err(Error::InvalidSamplingRatio(*ratio))
+            }
+            Self::ParentBased(root) => root.validate(),
+            _ => Ok(()),
+        }
+    }
+
+    /// Parse the values of the standard `OTEL_TRACES_SAMPLER` and `OTEL_TRACES_SAMPLER_ARG`
+    /// environment variables.
+    pub fn from_otel_env(sampler: &str, arg: Option<&str>) -> Result<Self, EnvVarError> {
+        let ratio = || match arg.map(|arg| (arg, arg.parse::<f64>())) {
+            Some((_, Ok(ratio))) if (0.0..=1.0).contains(&ratio) => Ok(ratio),
+            Some((arg, _)) => Err(EnvVarError::Invalid {
+                name: "OTEL_TRACES_SAMPLER_ARG",
+                value: arg.to_string(),
+                reason: "must be a ratio between 0.0 and 1.0".to_string(),
+            }),
+            // The specification defaults to 1.0 when the argument is missing
+            None => Ok(1.0),
+        };
+        let parent_based = |sampling: Self| Self::ParentBased(Box::new(sampling));
+
+        match sampler {
+            "always_on" => Ok(Self::AlwaysOn),
+            "always_off" => Ok(Self::AlwaysOff),
+            "traceidratio" => ratio().map(Self::TraceIdRatio),
+            "parentbased_always_on" => Ok(parent_based(Self::AlwaysOn)),
+            "parentbased_always_off" => Ok(parent_based(Self::AlwaysOff)),
+            "parentbased_traceidratio" => ratio().map(|ratio| parent_based(Self::TraceIdRatio(ratio))),
+            _ => Err(EnvVarError::Invalid {
+                name: "OTEL_TRACES_SAMPLER",
+                value: sampler.to_string(),
+                reason: "allowed values are 'always_on', 'always_off', 'traceidratio', 'parentbased_always_on', 'parentbased_always_off' and 'parentbased_traceidratio'".to_string(),
+            }),
+        }
+    }
+}
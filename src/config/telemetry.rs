@@ -0,0 +1,136 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt::{Display, Formatter},
+    str::FromStr,
+    time::Duration,
+};
+
+use super::{BatchConfig, TlsConfig};
+
+/// Configuration of the OpenTelemetry OTLP exporter.
+pub struct TelemetryConfig {
+    pub collector_url: String,
+    pub service_name: String,
+    pub protocol: TelemetryProtocol,
+    pub batch: BatchConfig,
+    /// Headers sent with every export request, eg. for authentication.
+    /// Values are percent-decoded by the HTTP exporter, as in `OTEL_EXPORTER_OTLP_HEADERS`.
+    pub headers: HashMap<String, String>,
+    /// Timeout of the export requests, defaults to `OTEL_EXPORTER_OTLP_TIMEOUT` or 10 seconds.
+    pub timeout: Option<Duration>,
+    /// Compress the export requests with gzip.
+    pub gzip_compression: bool,
+    /// TLS settings of the connection to the collector, when `None` the public roots are trusted.
+    pub tls: Option<TlsConfig>,
+}
+
+impl TelemetryConfig {
+    /// Create a [`TelemetryConfig`] exporting to `collector_url` with the default protocol.
+    pub fn new(collector_url: String, service_name: String) -> Self {
+        Self {
+            collector_url,
+            service_name,
+            protocol: TelemetryProtocol::default(),
+            batch: BatchConfig::default(),
+            headers: HashMap::new(),
+            timeout: None,
+            gzip_compression: false,
+            tls: None,
+        }
+    }
+
+    /// Set the protocol used to talk with the collector.
+    pub fn with_protocol(mut self, protocol: TelemetryProtocol) -> Self {
+        self.protocol = protocol;
+        self
+    }
+
+    /// Set the settings of the batch span processor.
+    pub fn with_batch_config(mut self, batch: BatchConfig) -> Self {
+        self.batch = batch;
+        self
+    }
+
+    /// Add a header sent with every export request, eg. `Authorization`.
+    /// Names and values are validated when the exporter is built.
+    pub fn with_header(mut self, name: String, value: String) -> Self {
+        self.headers.insert(name, value);
+        self
+    }
+
+    /// Set the timeout of the export requests.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Compress the export requests with gzip.
+    pub fn with_gzip_compression(mut self) -> Self {
+        self.gzip_compression = true;
+        self
+    }
+
+    /// Set the TLS settings of the connection to the collector.
+    pub fn with_tls_config(mut self, tls: TlsConfig) -> Self {
+        self.tls = Some(tls);
+        self
+    }
+}
+
+/// All the supported protocols for exporting telemetry data to the collector.
+///
+/// For the HTTP protocols the `/v1/traces` path is appended to the collector URL,
+/// while for gRPC the collector URL is used as is (eg. `http://localhost:4317`).
+#[derive(Eq, PartialEq, Debug, Clone, Copy, Default)]
+#[non_exhaustive]
+pub enum TelemetryProtocol {
+    /// Protobuf payloads over HTTP
+    #[default]
+    HttpBinary,
+    /// JSON payloads over HTTP
+    HttpJson,
+    /// gRPC via `tonic`, requires the `grpc` feature: without it configuring the subscriber
+    /// fails with [`crate::Error::GrpcNotEnabled`].
+    /// The subscriber must be configured from within a Tokio runtime.
+    Grpc,
+}
+
+impl FromStr for TelemetryProtocol {
+    type Err = TelemetryProtocolParseError;
+
+    /// Parse the values allowed by `OTEL_EXPORTER_OTLP_PROTOCOL`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "http/protobuf" => Ok(Self::HttpBinary),
+            "http/json" => Ok(Self::HttpJson),
+            "grpc" => Ok(Self::Grpc),
+            _ => Err(TelemetryProtocolParseError(s.to_string())),
+        }
+    }
+}
+
+impl Display for TelemetryProtocol {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let str = match self {
+            Self::HttpBinary => "http/protobuf",
+            Self::HttpJson => "http/json",
+            Self::Grpc => "grpc",
+        };
+        f.write_str(str)
+    }
+}
+
+#[derive(Debug)]
+pub struct TelemetryProtocolParseError(String);
+
+impl Error for TelemetryProtocolParseError {}
+
+impl Display for TelemetryProtocolParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!(
+            "{} is not a valid protocol string. Allowed strings are 'http/protobuf', 'http/json' and 'grpc'.",
+            &self.0
+        ))
+    }
+}
@@ -0,0 +1,45 @@
+use std::path::PathBuf;
+
+/// TLS settings of the connection to the collector.
+/// Certificates and keys are PEM files read when the exporter is built.
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct TlsConfig {
+    /// CA bundle used to verify the collector certificate, in addition to the public roots.
+    /// See `OTEL_EXPORTER_OTLP_CERTIFICATE`.
+    pub ca_certificate: Option<PathBuf>,
+    /// Client certificate presented to the collector for mutual TLS.
+    /// See `OTEL_EXPORTER_OTLP_CLIENT_CERTIFICATE`.
+    pub client_certificate: Option<PathBuf>,
+    /// Private key of the client certificate.
+    /// See `OTEL_EXPORTER_OTLP_CLIENT_KEY`.
+    pub client_key: Option<PathBuf>,
+    /// Skip the verification of the collector certificate.
+    /// Only allowed in [`crate::Environment::Dev`] and with the HTTP protocols.
+    pub insecure: bool,
+}
+
+impl TlsConfig {
+    /// Verify the collector certificate with the CA bundle at `path`.
+    pub fn with_ca_certificate(mut self, path: impl Into<PathBuf>) -> Self {
+        self.ca_certificate = Some(path.into());
+        self
+    }
+
+    /// Authenticate to the collector with the client certificate and key at the given paths.
+    pub fn with_client_identity(
+        mut self,
+        certificate: impl Into<PathBuf>,
+        key: impl Into<PathBuf>,
+    ) -> Self {
+        self.client_certificate = Some(certificate.into());
+        self.client_key = Some(key.into());
+        self
+    }
+
+    /// Accept any collector certificate, eg. a self-signed one on a local collector.
+    /// Configuring the subscriber fails outside of [`crate::Environment::Dev`].
+    pub fn with_insecure(mut self) -> Self {
+        self.insecure = true;
+        self
+    }
+}
@@ -0,0 +1,204 @@
+use std::fmt::{Display, Formatter};
+use std::path::PathBuf;
+
+use tracing::subscriber::SetGlobalDefaultError;
+use tracing_log::log::SetLoggerError;
+
+use crate::FromEnvError;
+
+/// Errors returned while configuring or initializing the subscriber.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// A global `log` logger has already been set.
+    LoggerAlreadySet(SetLoggerError),
+    /// A global default `tracing` subscriber has already been set.
+    SubscriberAlreadySet(SetGlobalDefaultError),
+    /// The filter directives are not valid.
+    InvalidFilter(tracing_subscriber::filter::ParseError),
+    /// The filter could not be changed, as the subscriber has been dropped.
+    FilterReload(tracing_subscriber::reload::Error),
+    /// The log file could not be opened or created.
+    OpenLogFile {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    /// The non-blocking writer failed to write the pending log lines in time.
+    WriterFlush,
+    /// The `traces` feature is enabled but no telemetry configuration has been provided.
+    MissingTelemetryConfig,
+    /// The collector URL is not a valid URL.
+    InvalidCollectorUrl {
+        url: String,
+        source: url::ParseError,
+    },
+    /// The gRPC protocol has been chosen without enabling the `grpc` feature.
+    GrpcNotEnabled,
+    /// An exporter header has a name or value not allowed in HTTP headers.
+    /// The value is not reported as it usually holds credentials.
+    InvalidHeader { name: String },
+    /// A certificate or key of the TLS configuration could not be read.
+    ReadTlsFile {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    /// The TLS configuration is not consistent or not allowed in the current environment.
+    InvalidTlsConfig(&'static str),
+    /// The ratio of a [`crate::Sampling::TraceIdRatio`] is not between `0.0` and `1.0`.
+    InvalidSamplingRatio(f64),
+    /// A custom redaction pattern is not a valid regular expression.
+    InvalidRedactionPattern { pattern: String, reason: String },
+    /// Redaction has been configured without the `redaction` feature, or without any of the
+    /// `json-logger` and `traces` features whose logs and spans can be redacted.
+    RedactionNotSupported,
+    /// Some of the `OTEL_*` environment variables read while configuring the exporters are not valid.
+    InvalidEnv(FromEnvError),
+    /// The TLS client could not be built, eg. because of an invalid certificate.
+    #[cfg(feature = "traces")]
+    TlsSetup(reqwest::Error),
+    /// The HTTP client of the exporter could not be built, as building it panicked.
+    #[cfg(feature = "traces")]
+    HttpClientPanic,
+    /// An OpenTelemetry provider (eg. `tracer`) failed to export its pending data in time.
+    #[cfg(feature = "traces")]
+    Flush {
+        provider: &'static str,
+        source: opentelemetry_sdk::error::OTelSdkError,
+    },
+    /// An OpenTelemetry provider (eg. `tracer`) failed to shut down in time.
+    #[cfg(feature = "traces")]
+    Shutdown {
+        provider: &'static str,
+        source: opentelemetry_sdk::error::OTelSdkError,
+    },
+    /// The OpenTelemetry OTLP exporter could not be built.
+    #[cfg(feature = "traces")]
+    ExporterBuild(opentelemetry_otlp::ExporterBuildError),
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::LoggerAlreadySet(err) => Some(err),
+            Self::SubscriberAlreadySet(err) => Some(err),
+            Self::InvalidFilter(err) => Some(err),
+            Self::FilterReload(err) => Some(err),
+            Self::OpenLogFile { source, .. } => Some(source),
+            Self::WriterFlush => None,
+            Self::MissingTelemetryConfig => None,
+            Self::InvalidCollectorUrl { source, .. } => Some(source),
+            Self::GrpcNotEnabled => None,
+            Self::InvalidHeader { .. } => None,
+            Self::ReadTlsFile { source, .. } => Some(source),
+            Self::InvalidTlsConfig(_) => None,
+            Self::InvalidSamplingRatio(_) => None,
+            Self::InvalidRedactionPattern { .. } => None,
+            Self::RedactionNotSupported => None,
+            Self::InvalidEnv(err) => Some(err),
+            #[cfg(feature = "traces")]
+            Self::TlsSetup(err) => Some(err),
+            #[cfg(feature = "traces")]
+            Self::HttpClientPanic => None,
+            #[cfg(feature = "traces")]
+            Self::Flush { source, .. } | Self::Shutdown { source, .. } => Some(source),
+            #[cfg(feature = "traces")]
+            Self::ExporterBuild(err) => Some(err),
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::LoggerAlreadySet(_) => f.write_str("Failed to set logger"),
+            Self::SubscriberAlreadySet(_) => f.write_str("Setting default subscriber failed"),
+            Self::InvalidFilter(_) => f.write_str("Invalid filter directives"),
+            Self::FilterReload(_) => f.write_str("Failed to change the filter"),
+            Self::OpenLogFile { path, .. } => {
+                write!(f, "Failed to open the log file {}", path.display())
+            }
+            Self::WriterFlush => f.write_str("Failed to write the pending log lines in time"),
+            Self::MissingTelemetryConfig => f.write_str(
+                "Telemetry config must be provided when the `traces` feature is enabled.",
+            ),
+            Self::InvalidCollectorUrl { url, .. } => {
+                write!(f, "{url} is not a valid collector URL")
+            }
+            Self::GrpcNotEnabled => {
+                f.write_str("The grpc protocol requires the `grpc` feature to be enabled")
+            }
+            Self::InvalidHeader { name } => {
+                write!(f, "The exporter header {name} has an invalid name or value")
+            }
+            Self::ReadTlsFile { path, .. } => {
+                write!(f, "Failed to read the TLS file {}", path.display())
+            }
+            Self::InvalidTlsConfig(reason) => write!(f, "Invalid TLS configuration: {reason}"),
+            Self::InvalidSamplingRatio(ratio) => {
+                write!(
+                    f,
+                    "{ratio} is not a valid sampling ratio, it must be between 0.0 and 1.0"
+                )
+            }
+            Self::InvalidRedactionPattern { pattern, reason } => {
+                write!(f, "{pattern} is not a valid redaction pattern: {reason}")
+            }
+            Self::RedactionNotSupported => f.write_str(
+                "Redaction requires the `redaction` feature and the `json-logger` or `traces` one",
+            ),
+            Self::InvalidEnv(_) => f.write_str("Invalid OpenTelemetry environment variables"),
+            #[cfg(feature = "traces")]
+            Self::TlsSetup(_) => {
+                f.write_str("Failed to configure TLS for the collector connection")
+            }
+            #[cfg(feature = "traces")]
+            Self::HttpClientPanic => f.write_str("Failed to build the HTTP client of the exporter"),
+            #[cfg(feature = "traces")]
+            Self::Flush { provider, .. } => {
+                write!(f, "Failed to flush the OpenTelemetry {provider} provider")
+            }
+            #[cfg(feature = "traces")]
+            Self::Shutdown { provider, .. } => {
+                write!(
+                    f,
+                    "Failed to shutdown the OpenTelemetry {provider} provider"
+                )
+            }
+            #[cfg(feature = "traces")]
+            Self::ExporterBuild(_) => {
+                f.write_str("Failed to configure the OpenTelemetry OTLP exporter")
+            }
+        }
+    }
+}
+
+impl From<SetLoggerError> for Error {
+    fn from(err: SetLoggerError) -> Self {
+        Self::LoggerAlreadySet(err)
+    }
+}
+
+impl From<SetGlobalDefaultError> for Error {
+    fn from(err: SetGlobalDefaultError) -> Self {
+        Self::SubscriberAlreadySet(err)
+    }
+}
+
+impl From<tracing_subscriber::filter::ParseError> for Error {
+    fn from(err: tracing_subscriber::filter::ParseError) -> Self {
+        Self::InvalidFilter(err)
+    }
+}
+
+impl From<tracing_subscriber::reload::Error> for Error {
+    fn from(err: tracing_subscriber::reload::Error) -> Self {
+        Self::FilterReload(err)
+    }
+}
+
+#[cfg(feature = "traces")]
+impl From<opentelemetry_otlp::ExporterBuildError> for Error {
+    fn from(err: opentelemetry_otlp::ExporterBuildError) -> Self {
+        Self::ExporterBuild(err)
+    }
+}
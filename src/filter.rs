@@ -0,0 +1,145 @@
+use tracing_subscriber::{reload, EnvFilter, Registry};
+
+use crate::{Environment, Error, FilterConfig};
+
+/// Handle changing at runtime the filter of a subscriber configured via
+/// [`crate::configure_subscriber`], eg. to enable debug logs while investigating an incident.
+/// It can be obtained with [`crate::Uninstall::filter_handle`] or
+/// [`crate::SubscriberHandles::filter_handle`] and only changes the subscriber it
+/// has been obtained for.
+#[derive(Clone)]
+pub struct FilterHandle(reload::Handle<EnvFilter, Registry>);
+
+impl FilterHandle {
+    /// Replace the filter with `directives` in the `RUST_LOG` format, eg. `info,my_crate=debug`.
+    /// The current filter is kept if any of the directives is not valid.
+    pub fn set_directives(&self, directives: &str) -> Result<(), Error> {
+        let filter = EnvFilter::try_new(directives)?;
+        self.0.reload(filter)?;
+        Ok(())
+    }
+
+    /// The directives of the current filter, `None` if the subscriber has been dropped.
+    pub fn directives(&self) -> Option<String> {
+        self.0.with_current(|filter| filter.to_string()).ok()
+    }
+}
+
+/// Build the filter from the directives in the configured environment variable, falling back
+/// to the default directives when it's not set.
+/// Invalid directives in the environment variable are ignored, as `EnvFilter::from_default_env`
+/// does, while invalid default directives are reported as an error.
+pub(crate) fn env_filter(config: &FilterConfig, env: Environment) -> Result<EnvFilter, Error> {
+    match std::env::var(&config.env_var) {
+        Ok(directives) if !directives.trim().is_empty() => {
+            Ok(EnvFilter::builder().parse_lossy(directives))
+        }
+        _ => {
+            let directives = config
+                .default_directives
+                .as_deref()
+                .unwrap_or_else(|| FilterConfig::default_directives_for(env));
+            Ok(EnvFilter::try_new(directives)?)
+        }
+    }
+}
+
+/// Per-layer filter applied on top of the global one, `None` lets everything through.
+pub(crate) fn layer_filter(directives: &Option<String>) -> Result<Option<EnvFilter>, Error> {
+    directives
+        .as_deref()
+        .map(EnvFilter::try_new)
+        .transpose()
+        .map_err(Error::from)
+}
+
+/// Wrap `filter` in a reloadable layer, returning the [`FilterHandle`] changing it.
+pub(crate) fn reloadable(filter: EnvFilter) -> (reload::Layer<EnvFilter, Registry>, FilterHandle) {
+    let (layer, handle) = reload::Layer::new(filter);
+    (layer, FilterHandle(handle))
+}
+
+#[cfg(test)]
+mod test {
+    use tracing::Level;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use super::*;
+
+    #[test]
+    fn set_directives_test() {
+        let (layer, handle) = reload::Layer::new(EnvFilter::new("error"));
+        let handle = FilterHandle(handle);
+        let subscriber = Registry::default().with(layer);
+
+        tracing::subscriber::with_default(subscriber, || {
+            assert!(!tracing::enabled!(Level::INFO));
+
+            handle.set_directives("info").unwrap();
+            assert!(tracing::enabled!(Level::INFO));
+            assert!(!tracing::enabled!(Level::DEBUG));
+            assert_eq!(handle.directives().as_deref(), Some("info"));
+
+            assert!(matches!(
+                handle.set_directives("info,my_crate=loud"),
+                Err(Error::InvalidFilter(_))
+            ));
+            assert_eq!(handle.directives().as_deref(), Some("info"));
+        });
+    }
+
+    #[test]
+    fn env_filter_defaults_test() {
+        let config = FilterConfig {
+            env_var: "PRIMA_TRACING_TEST_UNSET_FILTER".to_string(),
+            ..FilterConfig::default()
+        };
+
+        let filter = env_filter(&config, Environment::Production).unwrap();
+        assert_eq!(filter.to_string(), "info");
+
+        let filter = env_filter(&config, Environment::Dev).unwrap();
+        assert!(filter.to_string().contains("debug"));
+
+        let config = FilterConfig {
+            default_directives: Some("warn,my_crate=trace".to_string()),
+            ..config
+        };
+        let filter = env_filter(&config, Environment::Dev).unwrap();
+        assert_eq!(filter.max_level_hint(), Some(Level::TRACE.into()));
+
+        let config = FilterConfig {
+            default_directives: Some("my_crate=loud".to_string()),
+            ..config
+        };
+        assert!(matches!(
+            env_filter(&config, Environment::Dev),
+            Err(Error::InvalidFilter(_))
+        ));
+    }
+
+    #[test]
+    fn env_filter_custom_env_var_test() {
+        std::env::set_var("PRIMA_TRACING_TEST_FILTER", "warn");
+        let config = FilterConfig {
+            env_var: "PRIMA_TRACING_TEST_FILTER".to_string(),
+            default_directives: Some("trace".to_string()),
+            ..FilterConfig::default()
+        };
+
+        let filter = env_filter(&config, Environment::Dev).unwrap();
+        assert_eq!(filter.to_string(), "warn");
+    }
+
+    #[test]
+    fn layer_filter_test() {
+        assert!(layer_filter(&None).unwrap().is_none());
+        assert!(layer_filter(&Some("info,my_crate=debug".to_string()))
+            .unwrap()
+            .is_some());
+        assert!(matches!(
+            layer_filter(&Some("my_crate=loud".to_string())),
+            Err(Error::InvalidFilter(_))
+        ));
+    }
+}
@@ -0,0 +1,165 @@
+use serde_json::{Map, Value};
+use tracing::{Event, Metadata, Subscriber};
+use tracing_log::NormalizeEvent;
+use tracing_subscriber::{
+    layer::Context,
+    registry::{LookupSpan, SpanRef},
+};
+
+use crate::json::formatter::{scope_fields, span_fields, span_timings, SPAN_CLOSED_MESSAGE};
+use crate::json::storage::SpanTiming;
+use crate::subscriber::{ContextInfo, EventFormatter};
+
+/// Version of the Elastic Common Schema the logs comply with.
+const ECS_VERSION: &str = "8.11.0";
+
+/// [`EventFormatter`] writing JSON lines following the [Elastic Common Schema](https://www.elastic.co/guide/en/ecs/current/index.html),
+/// to be shipped to Elasticsearch.
+///
+/// The level, message, timestamp, target, service and trace/span ids are written with their ECS
+/// names, eg. `log.level` and `trace.id`, while the fields of the event and its spans are kept
+/// as they are. The country is written in `labels.country`.
+#[derive(Default)]
+pub struct EcsEventFormatter;
+
+impl EventFormatter for EcsEventFormatter {
+    fn format_event<S>(
+        &self,
+        event: &Event<'_>,
+        ctx: Context<'_, S>,
+        info: ContextInfo<'_>,
+    ) -> Result<Vec<u8>, std::io::Error>
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        let normalized_metadata = event.normalized_metadata();
+        let metadata = normalized_metadata
+            .as_ref()
+            .unwrap_or_else(|| event.metadata());
+
+        let mut visitor = info.json_visitor();
+        event.record(&mut visitor);
+
+        let mut line = span_fields(event, &ctx);
+        for (key, value) in visitor
+            .fields()
+            .iter()
+            .filter(|(&key, _)| key != "message" && !key.starts_with("log."))
+        {
+            line.insert(key.to_string(), value.clone());
+        }
+
+        let message = visitor.fields().get("message").cloned().unwrap_or_default();
+        let mut ecs = ecs_fields(metadata, message, info);
+
+        #[cfg(any(feature = "traces", feature = "datadog"))]
+        if let Some((trace_id, span_id)) = crate::json::formatter::otel_ids(&ctx) {
+            ecs.insert("trace.id".to_string(), Value::from(trace_id.to_string()));
+            ecs.insert("span.id".to_string(), Value::from(span_id.to_string()));
+        }
+
+        // The ECS fields are not overridden by the ones of the event with the same name
+        line.append(&mut ecs);
+        Ok(serde_json::to_vec(&line)?)
+    }
+
+    fn format_span_close<S>(
+        &self,
+        span: &SpanRef<'_, S>,
+        info: ContextInfo<'_>,
+    ) -> Result<Vec<u8>, std::io::Error>
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        let mut line = scope_fields(span);
+        for (key, value) in span_timings(span) {
+            line.insert(key.to_string(), Value::from(value));
+        }
+
+        let mut ecs = ecs_fields(span.metadata(), Value::from(SPAN_CLOSED_MESSAGE), info);
+        ecs.insert("span.name".to_string(), Value::from(span.name()));
+        if let Some(timing) = span.extensions().get::<SpanTiming>() {
+            // ECS durations are in nanoseconds
+            let duration = u64::try_from(timing.elapsed().as_nanos()).unwrap_or(u64::MAX);
+            ecs.insert("event.duration".to_string(), Value::from(duration));
+        }
+
+        line.append(&mut ecs);
+        Ok(serde_json::to_vec(&line)?)
+    }
+}
+
+/// Fields of the ECS line, from the metadata of the event or span.
+fn ecs_fields(
+    metadata: &Metadata<'_>,
+    message: Value,
+    info: ContextInfo<'_>,
+) -> Map<String, Value> {
+    let mut ecs = Map::new();
+    ecs.insert("@timestamp".to_string(), Value::from(now()));
+    ecs.insert(
+        "log.level".to_string(),
+        Value::from(metadata.level().to_string().to_lowercase()),
+    );
+    ecs.insert("message".to_string(), message);
+    ecs.insert("ecs.version".to_string(), Value::from(ECS_VERSION));
+    ecs.insert("log.logger".to_string(), Value::from(metadata.target()));
+    if let Some(file) = metadata.file() {
+        ecs.insert("log.origin.file.name".to_string(), Value::from(file));
+    }
+    if let Some(line) = metadata.line() {
+        ecs.insert("log.origin.file.line".to_string(), Value::from(line));
+    }
+    ecs.insert("service.name".to_string(), Value::from(info.app_name()));
+    ecs.insert(
+        "service.environment".to_string(),
+        Value::from(info.environment()),
+    );
+    ecs.insert(
+        "labels".to_string(),
+        serde_json::json!({ "country": info.country() }),
+    );
+    ecs
+}
+
+fn now() -> String {
+    chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::json::test::{format, format_span_close};
+
+    #[test]
+    fn ecs_format_test() {
+        let output = format(EcsEventFormatter);
+        let line: Value = serde_json::from_str(&output).unwrap();
+
+        assert!(line["@timestamp"].is_string());
+        assert_eq!(line["log.level"], "warn");
+        assert_eq!(line["message"], "Order created");
+        assert_eq!(line["ecs.version"], ECS_VERSION);
+        assert_eq!(line["log.logger"], "prima_tracing::json::test");
+        assert_eq!(line["service.name"], "app");
+        assert_eq!(line["service.environment"], "dev");
+        assert_eq!(line["labels"]["country"], "common");
+        assert_eq!(line["order_id"], 42);
+        assert_eq!(line["user_id"], 1);
+        assert_eq!(line["path"], "/orders");
+    }
+
+    #[test]
+    fn ecs_span_close_test() {
+        let lines = format_span_close(EcsEventFormatter);
+        assert_eq!(lines.len(), 1);
+
+        let line: Value = serde_json::from_str(&lines[0]).unwrap();
+        assert_eq!(line["message"], "span closed");
+        assert_eq!(line["log.level"], "info");
+        assert_eq!(line["span.name"], "request");
+        assert_eq!(line["path"], "/orders");
+        assert!(line["event.duration"].is_u64());
+        assert!(line["elapsed_ms"].is_f64());
+    }
+}
@@ -1,9 +1,12 @@
+use std::collections::HashMap;
 use std::io::Stdout;
 use std::io::Write;
+#[cfg(feature = "redaction")]
+use std::sync::Arc;
 
 use serde::ser::{SerializeMap, Serializer};
 use serde::Serialize;
-use tracing::{Event, Subscriber};
+use tracing::{span, Event, Level, Subscriber};
 use tracing_log::NormalizeEvent;
 use tracing_subscriber::{
     fmt::MakeWriter,
@@ -12,39 +15,49 @@ use tracing_subscriber::{
     Layer,
 };
 
-use crate::json::storage::PrimaJsonVisitor;
+use crate::json::storage::{PrimaJsonVisitor, SpanTiming};
+#[cfg(feature = "redaction")]
+use crate::redaction::Redactor;
 use crate::subscriber::{ContextInfo, EventFormatter};
 
-pub struct PrimaFormattingLayer<'writer, W: MakeWriter<'writer>, F: EventFormatter> {
-    make_writer: &'writer W,
+/// Message of the lines written when a span closes.
+pub(crate) const SPAN_CLOSED_MESSAGE: &str = "span closed";
+
+pub struct PrimaFormattingLayer<W: for<'writer> MakeWriter<'writer>, F: EventFormatter> {
+    make_writer: W,
     app_name: String,
     country: String,
     environment: String,
     formatter: F,
+    span_close_level: Option<Level>,
+    #[cfg(feature = "redaction")]
+    redactor: Option<Arc<Redactor>>,
 }
 
 /// Build a [`PrimaFormattingLayer`] layer with [`DefaultEventFormatter`] as format
 /// and [`std::io::Stdout`] as output
-pub fn layer<'writer>(
+pub fn layer(
     app_name: String,
     country: String,
     environment: String,
-) -> PrimaFormattingLayer<'writer, impl Fn() -> Stdout, DefaultEventFormatter> {
+) -> PrimaFormattingLayer<impl Fn() -> Stdout, DefaultEventFormatter> {
     PrimaFormattingLayer::new(
         app_name,
         country,
         environment,
-        &std::io::stdout,
-        DefaultEventFormatter,
+        std::io::stdout,
+        DefaultEventFormatter::default(),
     )
 }
 
-impl<'writer, W: MakeWriter<'writer>, F: EventFormatter> PrimaFormattingLayer<'writer, W, F> {
-    pub(crate) fn new(
+impl<W: for<'writer> MakeWriter<'writer>, F: EventFormatter> PrimaFormattingLayer<W, F> {
+    /// Build a [`PrimaFormattingLayer`] writing the events formatted by `formatter`
+    /// to the writers returned by `make_writer`, eg. [`std::io::stderr`] or a file.
+    pub fn new(
         app_name: String,
         country: String,
         environment: String,
-        make_writer: &'writer W,
+        make_writer: W,
         formatter: F,
     ) -> Self {
         Self {
@@ -53,20 +66,69 @@ impl<'writer, W: MakeWriter<'writer>, F: EventFormatter> PrimaFormattingLayer<'w
             country,
             environment,
             formatter,
+            span_close_level: None,
+            #[cfg(feature = "redaction")]
+            redactor: None,
         }
     }
 
-    pub fn with_formatter<A: EventFormatter>(
-        self,
-        formatter: A,
-    ) -> PrimaFormattingLayer<'writer, W, A> {
-        PrimaFormattingLayer::new(
-            self.app_name,
-            self.country,
-            self.environment,
-            self.make_writer,
+    pub fn with_formatter<A: EventFormatter>(self, formatter: A) -> PrimaFormattingLayer<W, A> {
+        PrimaFormattingLayer {
+            make_writer: self.make_writer,
+            app_name: self.app_name,
+            country: self.country,
+            environment: self.environment,
             formatter,
-        )
+            span_close_level: self.span_close_level,
+            #[cfg(feature = "redaction")]
+            redactor: self.redactor,
+        }
+    }
+
+    /// Write the formatted events to the writers returned by `make_writer` instead.
+    pub fn with_writer<M: for<'writer> MakeWriter<'writer>>(
+        self,
+        make_writer: M,
+    ) -> PrimaFormattingLayer<M, F> {
+        PrimaFormattingLayer {
+            make_writer,
+            app_name: self.app_name,
+            country: self.country,
+            environment: self.environment,
+            formatter: self.formatter,
+            span_close_level: self.span_close_level,
+            #[cfg(feature = "redaction")]
+            redactor: self.redactor,
+        }
+    }
+
+    /// Write a line with the elapsed, busy and idle time of the spans up to `level` when they
+    /// close, formatted by [`EventFormatter::format_span_close`]. Useful to analyse the latency
+    /// from the logs when the traces are sampled out.
+    pub fn with_span_close_events(mut self, level: Level) -> Self {
+        self.span_close_level = Some(level);
+        self
+    }
+
+    pub(crate) fn with_span_close_level(mut self, level: Option<Level>) -> Self {
+        self.span_close_level = level;
+        self
+    }
+
+    #[cfg(feature = "redaction")]
+    pub(crate) fn with_redactor(mut self, redactor: Option<Arc<Redactor>>) -> Self {
+        self.redactor = redactor;
+        self
+    }
+
+    fn context_info(&self) -> ContextInfo<'_> {
+        ContextInfo {
+            app_name: self.app_name.as_str(),
+            country: self.country.as_str(),
+            environment: self.environment.as_str(),
+            #[cfg(feature = "redaction")]
+            redactor: self.redactor.as_ref(),
+        }
     }
 
     fn emit(&self, mut buffer: Vec<u8>) -> Result<(), std::io::Error> {
@@ -82,22 +144,14 @@ impl<'writer, W: MakeWriter<'writer>, F: EventFormatter> PrimaFormattingLayer<'w
     where
         S: Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>,
     {
-        self.formatter.format_event(
-            event,
-            ctx,
-            ContextInfo {
-                app_name: self.app_name.as_str(),
-                country: self.country.as_str(),
-                environment: self.environment.as_str(),
-            },
-        )
+        self.formatter.format_event(event, ctx, self.context_info())
     }
 }
 
-impl<S, W, F: 'static> Layer<S> for PrimaFormattingLayer<'static, W, F>
+impl<S, W, F: 'static> Layer<S> for PrimaFormattingLayer<W, F>
 where
     S: Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>,
-    W: MakeWriter<'static>,
+    W: for<'writer> MakeWriter<'writer> + 'static,
     F: EventFormatter,
 {
     fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
@@ -105,9 +159,216 @@ where
             let _ = self.emit(serialized);
         }
     }
+
+    fn on_close(&self, id: span::Id, ctx: Context<'_, S>) {
+        let Some(level) = self.span_close_level else {
+            return;
+        };
+        let Some(span) = ctx
+            .span(&id)
+            .filter(|span| *span.metadata().level() <= level)
+        else {
+            return;
+        };
+        if let Ok(serialized) = self.formatter.format_span_close(&span, self.context_info()) {
+            if !serialized.is_empty() {
+                let _ = self.emit(serialized);
+            }
+        }
+    }
+}
+
+/// JSON [`EventFormatter`] used by default with the `json-logger` feature.
+///
+/// The layout of the lines can be changed with [`DefaultEventFormatter::builder`].
+#[derive(Default)]
+pub struct DefaultEventFormatter {
+    layout: FieldLayout,
+    #[cfg(any(feature = "traces", feature = "datadog"))]
+    otel_ids: bool,
+}
+
+impl DefaultEventFormatter {
+    /// Create a [`DefaultEventFormatterBuilder`] to customize the keys and the fields of the lines.
+    pub fn builder() -> DefaultEventFormatterBuilder {
+        DefaultEventFormatterBuilder::default()
+    }
+
+    /// Add the `trace_id` and `span_id` of the current span as hex strings, and its
+    /// `trace_flags`, at root level as defined by the OpenTelemetry logs data model.
+    /// Unlike the `dd` ids, they don't require the `datadog` feature.
+    #[cfg(any(feature = "traces", feature = "datadog"))]
+    pub fn with_otel_ids(mut self) -> Self {
+        self.otel_ids = true;
+        self
+    }
+
+    /// Whether a flattened span field named `key` would collide with a key written by the
+    /// formatter itself, in which case it is skipped.
+    fn is_reserved_key(&self, key: &str) -> bool {
+        if key == "message" || self.layout.is_root_key(key) {
+            return true;
+        }
+        #[cfg(any(feature = "traces", feature = "datadog"))]
+        if self.otel_ids && ["trace_id", "span_id", "trace_flags"].contains(&key) {
+            return true;
+        }
+        #[cfg(feature = "datadog")]
+        if key == "dd" {
+            return true;
+        }
+        false
+    }
+}
+
+/// Root keys written by [`DefaultEventFormatter`] that can be renamed with
+/// [`DefaultEventFormatterBuilder::with_key`].
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
+pub enum RootKey {
+    /// `timestamp`
+    Timestamp,
+    /// `level`
+    Level,
+    /// `country`
+    Country,
+    /// `environment`
+    Environment,
+    /// `type`, the name of the app
+    AppName,
+    /// `message`
+    Message,
+    /// `current_span`
+    CurrentSpan,
+    /// `spans`
+    Spans,
 }
 
-pub struct DefaultEventFormatter;
+impl RootKey {
+    const ALL: [RootKey; 8] = [
+        RootKey::Timestamp,
+        RootKey::Level,
+        RootKey::Country,
+        RootKey::Environment,
+        RootKey::AppName,
+        RootKey::Message,
+        RootKey::CurrentSpan,
+        RootKey::Spans,
+    ];
+
+    fn default_name(self) -> &'static str {
+        match self {
+            RootKey::Timestamp => "timestamp",
+            RootKey::Level => "level",
+            RootKey::Country => "country",
+            RootKey::Environment => "environment",
+            RootKey::AppName => "type",
+            RootKey::Message => "message",
+            RootKey::CurrentSpan => "current_span",
+            RootKey::Spans => "spans",
+        }
+    }
+}
+
+struct FieldLayout {
+    keys: HashMap<RootKey, String>,
+    spans: bool,
+    current_span: bool,
+    source_location: bool,
+    flatten_span_fields: bool,
+    log_metadata: bool,
+}
+
+impl Default for FieldLayout {
+    fn default() -> Self {
+        Self {
+            keys: HashMap::new(),
+            spans: true,
+            current_span: true,
+            source_location: true,
+            flatten_span_fields: false,
+            log_metadata: false,
+        }
+    }
+}
+
+impl FieldLayout {
+    fn key(&self, key: RootKey) -> &str {
+        self.keys
+            .get(&key)
+            .map_or(key.default_name(), String::as_str)
+    }
+
+    fn is_root_key(&self, name: &str) -> bool {
+        RootKey::ALL.iter().any(|&key| self.key(key) == name)
+    }
+}
+
+/// Builder of a [`DefaultEventFormatter`] with a custom layout, created via
+/// [`DefaultEventFormatter::builder`]. The defaults match [`DefaultEventFormatter::default`].
+#[derive(Default)]
+pub struct DefaultEventFormatterBuilder {
+    layout: FieldLayout,
+    #[cfg(any(feature = "traces", feature = "datadog"))]
+    otel_ids: bool,
+}
+
+impl DefaultEventFormatterBuilder {
+    /// Write the value of `key` with the given name, eg. `service` instead of `type` for the app name.
+    pub fn with_key(mut self, key: RootKey, name: impl Into<String>) -> Self {
+        self.layout.keys.insert(key, name.into());
+        self
+    }
+
+    /// Whether to write the list of the spans containing the event, enabled by default.
+    pub fn with_spans(mut self, spans: bool) -> Self {
+        self.layout.spans = spans;
+        self
+    }
+
+    /// Whether to write the span containing the event, enabled by default.
+    pub fn with_current_span(mut self, current_span: bool) -> Self {
+        self.layout.current_span = current_span;
+        self
+    }
+
+    /// Whether to write the `file`, `line` and `target` of the spans, enabled by default.
+    pub fn with_source_location(mut self, source_location: bool) -> Self {
+        self.layout.source_location = source_location;
+        self
+    }
+
+    /// Whether to write the fields of the spans containing the event at root level, disabled by
+    /// default. The fields of the inner spans override the ones of the outer spans, and the
+    /// fields of the event override both. Span fields named like a key written by the formatter,
+    /// eg. `level` or `message`, are skipped.
+    pub fn with_flattened_span_fields(mut self, flatten_span_fields: bool) -> Self {
+        self.layout.flatten_span_fields = flatten_span_fields;
+        self
+    }
+
+    /// Whether to keep the `log.*` fields added to the events coming from the `log` crate,
+    /// eg. `log.file` and `log.line`, disabled by default.
+    pub fn with_log_metadata(mut self, log_metadata: bool) -> Self {
+        self.layout.log_metadata = log_metadata;
+        self
+    }
+
+    /// Whether to write the OpenTelemetry ids of the current span at root level, disabled by
+    /// default. See [`DefaultEventFormatter::with_otel_ids`].
+    #[cfg(any(feature = "traces", feature = "datadog"))]
+    pub fn with_otel_ids(mut self, otel_ids: bool) -> Self {
+        self.otel_ids = otel_ids;
+        self
+    }
+
+    pub fn build(self) -> DefaultEventFormatter {
+        DefaultEventFormatter {
+            layout: self.layout,
+            #[cfg(any(feature = "traces", feature = "datadog"))]
+            otel_ids: self.otel_ids,
+        }
+    }
+}
 
 impl EventFormatter for DefaultEventFormatter {
     fn format_event<S>(
@@ -128,83 +389,253 @@ impl EventFormatter for DefaultEventFormatter {
         let mut buffer = Vec::new();
         let mut serializer = serde_json::Serializer::new(&mut buffer);
         let mut map_serializer = serializer.serialize_map(None)?;
-        let mut visitor = PrimaJsonVisitor::default();
+        let mut visitor = info.json_visitor();
         event.record(&mut visitor);
+        let layout = &self.layout;
 
-        for (key, value) in visitor
-            .fields()
-            .iter()
-            .filter(|(&key, _)| key != "message" && !key.starts_with("log."))
-        {
+        if layout.flatten_span_fields {
+            for (key, value) in span_fields(event, &ctx) {
+                if !self.is_reserved_key(&key) && !visitor.fields().contains_key(key.as_str()) {
+                    map_serializer.serialize_entry(&key, &value)?;
+                }
+            }
+        }
+
+        for (key, value) in visitor.fields().iter().filter(|(&key, _)| {
+            key != "message" && (layout.log_metadata || !key.starts_with("log."))
+        }) {
             map_serializer.serialize_entry(key, value)?;
         }
 
-        map_serializer.serialize_entry("timestamp", &chrono::Utc::now())?;
+        map_serializer.serialize_entry(layout.key(RootKey::Timestamp), &chrono::Utc::now())?;
         map_serializer.serialize_entry(
-            "level",
+            layout.key(RootKey::Level),
             metadata.level().to_string().to_lowercase().as_str(),
         )?;
-        map_serializer.serialize_entry("country", info.country())?;
-        map_serializer.serialize_entry("environment", info.environment())?;
-        map_serializer.serialize_entry("type", info.app_name())?;
+        map_serializer.serialize_entry(layout.key(RootKey::Country), info.country())?;
+        map_serializer.serialize_entry(layout.key(RootKey::Environment), info.environment())?;
+        map_serializer.serialize_entry(layout.key(RootKey::AppName), info.app_name())?;
 
-        map_serializer.serialize_entry("message", &visitor.fields().get("message"))?;
+        map_serializer.serialize_entry(
+            layout.key(RootKey::Message),
+            &visitor.fields().get("message"),
+        )?;
+
+        if layout.current_span {
+            if let Some(current_span) = ctx.current_span().id().and_then(|id| ctx.span(id)) {
+                map_serializer.serialize_entry(
+                    layout.key(RootKey::CurrentSpan),
+                    &SpanSerializer(&current_span, layout.source_location),
+                )?;
+            }
+        }
 
-        if let Some(current_span) = ctx.current_span().id().and_then(|id| ctx.span(id)) {
-            map_serializer.serialize_entry("current_span", &SpanSerializer(&current_span))?;
+        if layout.spans {
+            map_serializer.serialize_entry(
+                layout.key(RootKey::Spans),
+                &SpanListSerializer(&ctx, layout.source_location),
+            )?;
         }
 
-        map_serializer.serialize_entry("spans", &SpanListSerializer(&ctx))?;
+        #[cfg(any(feature = "traces", feature = "datadog"))]
+        if self.otel_ids {
+            if let Some((trace_id, span_id)) = otel_ids(&ctx) {
+                map_serializer.serialize_entry("trace_id", &trace_id.to_string())?;
+                map_serializer.serialize_entry("span_id", &span_id.to_string())?;
+                if let Some(trace_flags) = trace_flags(span_id) {
+                    map_serializer.serialize_entry("trace_flags", &trace_flags)?;
+                }
+            }
+        }
 
         // Adds support for correlating logs and traces on datadog
         // In order for Datadog to be able to correlate the logs with the traces we need to insert `dd.trace_id` and `dd.span_id` at root level
         // https://docs.datadoghq.com/tracing/connect_logs_and_traces/opentelemetry/
         #[cfg(feature = "datadog")]
-        {
-            use opentelemetry::trace::TraceContextExt;
-            use std::collections::HashMap;
-            use tracing_opentelemetry::{OpenTelemetrySpanExt, OtelData};
+        if let Some((trace_id, span_id)) = datadog_ids(&ctx) {
+            let mut dd = std::collections::HashMap::new();
+            dd.insert("trace_id", trace_id);
+            dd.insert("span_id", span_id);
 
-            if let Some(current_span) = ctx.current_span().id().and_then(|id| ctx.span(id)) {
-                let ext = current_span.extensions();
-
-                if let Some(otel_data) = ext.get::<OtelData>() {
-                    let mut trace_id_opt = otel_data.trace_id();
-                    let mut span_id_opt = otel_data.span_id();
-
-                    if trace_id_opt.is_none() || span_id_opt.is_none() {
-                        let ctx = tracing::Span::current().context();
-                        let span = ctx.span();
-                        let sctx = span.span_context();
-
-                        if sctx.is_valid() {
-                            trace_id_opt = Some(sctx.trace_id());
-                            span_id_opt = Some(sctx.span_id());
-                        }
-                    }
-
-                    if let (Some(trace_id), Some(span_id)) = (trace_id_opt, span_id_opt) {
-                        // Datadog trace and span IDs need to be 64-bit unsigned integers
-                        let trace_id_u64 = u128::from_be_bytes(trace_id.to_bytes()) as u64;
-                        let span_id_u64 = u64::from_be_bytes(span_id.to_bytes());
-
-                        let mut dd = HashMap::new();
-                        dd.insert("trace_id", trace_id_u64);
-                        dd.insert("span_id", span_id_u64);
-
-                        map_serializer.serialize_entry("dd", &dd)?;
-                    }
+            map_serializer.serialize_entry("dd", &dd)?;
+        }
+
+        map_serializer.end()?;
+
+        Ok(buffer)
+    }
+
+    fn format_span_close<S>(
+        &self,
+        span: &SpanRef<'_, S>,
+        info: ContextInfo<'_>,
+    ) -> Result<Vec<u8>, std::io::Error>
+    where
+        S: Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>,
+    {
+        let layout = &self.layout;
+        let mut buffer = Vec::new();
+        let mut serializer = serde_json::Serializer::new(&mut buffer);
+        let mut map_serializer = serializer.serialize_map(None)?;
+        let timings = span_timings(span);
+
+        if layout.flatten_span_fields {
+            for (key, value) in scope_fields(span) {
+                if !self.is_reserved_key(&key) && timings.iter().all(|(name, _)| *name != key) {
+                    map_serializer.serialize_entry(&key, &value)?;
                 }
             }
         }
 
+        map_serializer.serialize_entry(layout.key(RootKey::Timestamp), &chrono::Utc::now())?;
+        map_serializer.serialize_entry(
+            layout.key(RootKey::Level),
+            span.metadata().level().to_string().to_lowercase().as_str(),
+        )?;
+        map_serializer.serialize_entry(layout.key(RootKey::Country), info.country())?;
+        map_serializer.serialize_entry(layout.key(RootKey::Environment), info.environment())?;
+        map_serializer.serialize_entry(layout.key(RootKey::AppName), info.app_name())?;
+        map_serializer.serialize_entry(layout.key(RootKey::Message), SPAN_CLOSED_MESSAGE)?;
+
+        if layout.current_span {
+            map_serializer.serialize_entry(
+                layout.key(RootKey::CurrentSpan),
+                &SpanSerializer(span, layout.source_location),
+            )?;
+        }
+
+        for (key, value) in timings {
+            map_serializer.serialize_entry(key, &value)?;
+        }
+
         map_serializer.end()?;
 
         Ok(buffer)
     }
 }
 
-struct SpanSerializer<'a, 'b, Span>(&'b SpanRef<'a, Span>)
+/// Elapsed, busy and idle time of `span` in milliseconds, see [`SpanTiming`].
+pub(crate) fn span_timings<S>(span: &SpanRef<'_, S>) -> Vec<(&'static str, f64)>
+where
+    S: for<'a> tracing_subscriber::registry::LookupSpan<'a>,
+{
+    let millis = |duration: std::time::Duration| duration.as_micros() as f64 / 1000.0;
+
+    span.extensions()
+        .get::<SpanTiming>()
+        .map(|timing| {
+            vec![
+                ("elapsed_ms", millis(timing.elapsed())),
+                ("busy_ms", millis(timing.busy())),
+                ("idle_ms", millis(timing.idle())),
+            ]
+        })
+        .unwrap_or_default()
+}
+
+/// Trace and span ids of the current span in the format used by Datadog,
+/// in order to correlate the logs with the traces.
+#[cfg(feature = "datadog")]
+pub(crate) fn datadog_ids<S>(ctx: &Context<'_, S>) -> Option<(u64, u64)>
+where
+    S: Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>,
+{
+    let (trace_id, span_id) = otel_ids(ctx)?;
+    // Datadog trace and span IDs need to be 64-bit unsigned integers
+    let trace_id_u64 = u128::from_be_bytes(trace_id.to_bytes()) as u64;
+    let span_id_u64 = u64::from_be_bytes(span_id.to_bytes());
+
+    Some((trace_id_u64, span_id_u64))
+}
+
+/// OpenTelemetry trace and span ids of the current span.
+#[cfg(any(feature = "traces", feature = "datadog"))]
+pub(crate) fn otel_ids<S>(
+    ctx: &Context<'_, S>,
+) -> Option<(opentelemetry::trace::TraceId, opentelemetry::trace::SpanId)>
+where
+    S: Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>,
+{
+    use opentelemetry::trace::TraceContextExt;
+    use tracing_opentelemetry::{OpenTelemetrySpanExt, OtelData};
+
+    let current_span = ctx.current_span().id().and_then(|id| ctx.span(id))?;
+    let ext = current_span.extensions();
+    let otel_data = ext.get::<OtelData>()?;
+
+    let mut trace_id_opt = otel_data.trace_id();
+    let mut span_id_opt = otel_data.span_id();
+
+    if trace_id_opt.is_none() || span_id_opt.is_none() {
+        let ctx = tracing::Span::current().context();
+        let span = ctx.span();
+        let sctx = span.span_context();
+
+        if sctx.is_valid() {
+            trace_id_opt = Some(sctx.trace_id());
+            span_id_opt = Some(sctx.span_id());
+        }
+    }
+
+    Some((trace_id_opt?, span_id_opt?))
+}
+
+/// Trace flags of the span `span_id` as a hex string, eg. `01` when sampled.
+///
+/// `OtelData` doesn't expose them, so they are read from the OpenTelemetry context activated
+/// when entering the span, as long as it's the entered one.
+#[cfg(any(feature = "traces", feature = "datadog"))]
+fn trace_flags(span_id: opentelemetry::trace::SpanId) -> Option<String> {
+    use opentelemetry::trace::TraceContextExt;
+
+    let context = opentelemetry::Context::current();
+    let span = context.span();
+    let span_context = span.span_context();
+    (span_context.span_id() == span_id)
+        .then(|| format!("{:02x}", span_context.trace_flags().to_u8()))
+}
+
+/// Fields of the spans containing `event`, from the root one, the inner spans overriding the
+/// fields of the outer ones with the same name.
+pub(crate) fn span_fields<S>(
+    event: &Event<'_>,
+    ctx: &Context<'_, S>,
+) -> serde_json::Map<String, serde_json::Value>
+where
+    S: Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>,
+{
+    ctx.event_span(event)
+        .map(|span| scope_fields(&span))
+        .unwrap_or_default()
+}
+
+/// Fields of `span` and of the spans containing it, merged as in [`span_fields`]. They are
+/// read from the span itself when propagated by [`crate::json::storage::PrimaJsonStorage`].
+pub(crate) fn scope_fields<S>(span: &SpanRef<'_, S>) -> serde_json::Map<String, serde_json::Value>
+where
+    S: for<'a> tracing_subscriber::registry::LookupSpan<'a>,
+{
+    let mut fields = serde_json::Map::new();
+    if let Some(visitor) = span.extensions().get::<PrimaJsonVisitor>() {
+        if visitor.is_propagated() {
+            for (key, value) in visitor.merged_fields() {
+                fields.insert(key.to_string(), value);
+            }
+            return fields;
+        }
+    }
+    for span in span.scope().from_root() {
+        if let Some(visitor) = span.extensions().get::<PrimaJsonVisitor>() {
+            for (key, value) in visitor.fields() {
+                fields.insert(key.to_string(), value.clone());
+            }
+        }
+    }
+    fields
+}
+
+/// Serializes the name and the fields of a span, and its source location when the flag is set.
+struct SpanSerializer<'a, 'b, Span>(&'b SpanRef<'a, Span>, bool)
 where
     Span: for<'lookup> LookupSpan<'lookup>;
 
@@ -219,9 +650,11 @@ where
         let mut serializer = serializer.serialize_map(None)?;
 
         serializer.serialize_entry("name", self.0.metadata().name())?;
-        serializer.serialize_entry("line", &self.0.metadata().line())?;
-        serializer.serialize_entry("target", &self.0.metadata().target())?;
-        serializer.serialize_entry("file", &self.0.metadata().file())?;
+        if self.1 {
+            serializer.serialize_entry("line", &self.0.metadata().line())?;
+            serializer.serialize_entry("target", &self.0.metadata().target())?;
+            serializer.serialize_entry("file", &self.0.metadata().file())?;
+        }
 
         if let Some(visitor) = self.0.extensions().get::<PrimaJsonVisitor>() {
             for (key, value) in visitor.fields().iter() {
@@ -233,7 +666,7 @@ where
     }
 }
 
-struct SpanListSerializer<'a, 'b, S>(&'b Context<'a, S>)
+struct SpanListSerializer<'a, 'b, S>(&'b Context<'a, S>, bool)
 where
     S: Subscriber + for<'lookup> LookupSpan<'lookup>;
 
@@ -256,7 +689,7 @@ where
             for span in span_root {
                 serde::ser::SerializeSeq::serialize_element(
                     &mut serializer,
-                    &SpanSerializer(&span),
+                    &SpanSerializer(&span, self.1),
                 )?;
             }
         }
@@ -264,3 +697,247 @@ where
         serde::ser::SerializeSeq::end(serializer)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use serde_json::Value;
+
+    use super::*;
+    use crate::json::test::format;
+
+    fn format_json(formatter: DefaultEventFormatter) -> Value {
+        serde_json::from_str(&format(formatter)).unwrap()
+    }
+
+    #[test]
+    fn default_layout_test() {
+        let line = format_json(DefaultEventFormatter::default());
+
+        assert_eq!(line["type"], "app");
+        assert_eq!(line["message"], "Order created");
+        assert_eq!(line["order_id"], 42);
+        assert!(line.get("user_id").is_none());
+        assert_eq!(line["current_span"]["name"], "create_order");
+        assert_eq!(line["current_span"]["user_id"], 1);
+        assert!(line["current_span"]["file"].is_string());
+        assert_eq!(line["spans"].as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn custom_layout_test() {
+        let line = format_json(
+            DefaultEventFormatter::builder()
+                .with_key(RootKey::AppName, "service")
+                .with_key(RootKey::Message, "msg")
+                .with_spans(false)
+                .with_source_location(false)
+                .with_flattened_span_fields(true)
+                .build(),
+        );
+
+        assert_eq!(line["service"], "app");
+        assert!(line.get("type").is_none());
+        assert_eq!(line["msg"], "Order created");
+        assert!(line.get("spans").is_none());
+        assert_eq!(line["current_span"]["name"], "create_order");
+        assert!(line["current_span"].get("file").is_none());
+        assert_eq!(line["path"], "/orders");
+        assert_eq!(line["user_id"], 1);
+        assert_eq!(line["order_id"], 42);
+    }
+
+    #[test]
+    fn flattened_propagated_span_fields_test() {
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let buffer = crate::json::test::Buffer::default();
+        let formatter = DefaultEventFormatter::builder()
+            .with_spans(false)
+            .with_current_span(false)
+            .with_flattened_span_fields(true)
+            .build();
+        let subscriber = tracing_subscriber::Registry::default()
+            .with(crate::json::storage::PrimaJsonStorage::default().with_field_propagation())
+            .with(PrimaFormattingLayer::new(
+                "app".to_string(),
+                "common".to_string(),
+                "dev".to_string(),
+                buffer.clone(),
+                formatter,
+            ));
+
+        tracing::subscriber::with_default(subscriber, || {
+            let request = tracing::info_span!("request", path = "/orders", user_id = 1);
+            request.in_scope(|| {
+                let create_order = tracing::info_span!("create_order", user_id = 2);
+                // Not propagated to the existing child span
+                request.record("path", "/carts");
+                create_order.in_scope(|| tracing::info!("Order created"));
+            });
+        });
+
+        let line: Value = serde_json::from_str(&buffer.contents()).unwrap();
+        assert_eq!(line["path"], "/orders");
+        assert_eq!(line["user_id"], 2);
+    }
+
+    #[test]
+    fn flattened_span_fields_collisions_test() {
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let buffer = crate::json::test::Buffer::default();
+        let formatter = DefaultEventFormatter::builder()
+            .with_key(RootKey::AppName, "service")
+            .with_current_span(false)
+            .with_spans(false)
+            .with_flattened_span_fields(true)
+            .build();
+        let subscriber = tracing_subscriber::Registry::default()
+            .with(crate::json::storage::PrimaJsonStorage::default())
+            .with(
+                PrimaFormattingLayer::new(
+                    "app".to_string(),
+                    "common".to_string(),
+                    "dev".to_string(),
+                    buffer.clone(),
+                    formatter,
+                )
+                .with_span_close_events(Level::INFO),
+            );
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info_span!(
+                "request",
+                message = "from span",
+                level = "from span",
+                service = "from span",
+                elapsed_ms = 0,
+                user = "from span",
+                path = "/orders"
+            )
+            .in_scope(|| tracing::info!(user = "from event", "Order created"));
+        });
+
+        let contents = buffer.contents();
+        let raw_lines: Vec<&str> = contents.lines().collect();
+        assert_eq!(raw_lines.len(), 2);
+        for raw in &raw_lines {
+            for key in ["message", "level", "service", "user", "elapsed_ms"] {
+                assert!(raw.matches(&format!("\"{key}\":")).count() <= 1, "{raw}");
+            }
+        }
+
+        let event: Value = serde_json::from_str(raw_lines[0]).unwrap();
+        assert_eq!(event["message"], "Order created");
+        assert_eq!(event["level"], "info");
+        assert_eq!(event["service"], "app");
+        assert_eq!(event["user"], "from event");
+        assert_eq!(event["path"], "/orders");
+
+        let span_close: Value = serde_json::from_str(raw_lines[1]).unwrap();
+        assert_eq!(span_close["message"], SPAN_CLOSED_MESSAGE);
+        assert_eq!(span_close["level"], "info");
+        assert!(span_close["elapsed_ms"].is_f64());
+        assert_eq!(span_close["user"], "from span");
+    }
+
+    #[test]
+    fn log_metadata_test() {
+        use tracing_log::log;
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let output = |log_metadata| {
+            let buffer = crate::json::test::Buffer::default();
+            let formatter = DefaultEventFormatter::builder()
+                .with_current_span(false)
+                .with_log_metadata(log_metadata)
+                .build();
+            let subscriber =
+                tracing_subscriber::Registry::default().with(PrimaFormattingLayer::new(
+                    "app".to_string(),
+                    "common".to_string(),
+                    "dev".to_string(),
+                    buffer.clone(),
+                    formatter,
+                ));
+            tracing::subscriber::with_default(subscriber, || {
+                let record = log::Record::builder()
+                    .args(format_args!("From log"))
+                    .level(log::Level::Info)
+                    .target("legacy")
+                    .file(Some("legacy.rs"))
+                    .line(Some(7))
+                    .build();
+                tracing_log::format_trace(&record).unwrap();
+            });
+            serde_json::from_str::<Value>(&buffer.contents()).unwrap()
+        };
+
+        assert!(output(false).get("log.file").is_none());
+        assert_eq!(output(true)["log.file"], "legacy.rs");
+        assert_eq!(output(true)["message"], "From log");
+    }
+
+    #[test]
+    fn span_close_test() {
+        let lines = crate::json::test::format_span_close(DefaultEventFormatter::default());
+        assert_eq!(lines.len(), 1);
+
+        let line: Value = serde_json::from_str(&lines[0]).unwrap();
+        assert_eq!(line["message"], SPAN_CLOSED_MESSAGE);
+        assert_eq!(line["level"], "info");
+        assert_eq!(line["type"], "app");
+        assert_eq!(line["current_span"]["name"], "request");
+        assert_eq!(line["current_span"]["path"], "/orders");
+        assert!(line["elapsed_ms"].is_f64());
+        assert!(line["busy_ms"].is_f64());
+        assert!(line["idle_ms"].is_f64());
+    }
+
+    #[cfg(feature = "traces")]
+    #[test]
+    fn otel_ids_test() {
+        use opentelemetry::trace::TracerProvider;
+        use tracing_subscriber::layer::SubscriberExt;
+
+        use crate::json::{storage::PrimaJsonStorage, test::Buffer};
+
+        let buffer = Buffer::default();
+        let provider = opentelemetry_sdk::trace::SdkTracerProvider::builder().build();
+        let subscriber = tracing_subscriber::Registry::default()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")))
+            .with(PrimaJsonStorage::default())
+            .with(PrimaFormattingLayer::new(
+                "app".to_string(),
+                "common".to_string(),
+                "dev".to_string(),
+                buffer.clone(),
+                DefaultEventFormatter::builder()
+                    .with_key(RootKey::Message, "msg")
+                    .with_otel_ids(true)
+                    .build(),
+            ));
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!("outside of spans");
+            tracing::info_span!("span").in_scope(|| tracing::info!("within a span"));
+        });
+
+        let contents = buffer.contents();
+        let lines: Vec<Value> = contents
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+
+        assert!(lines[0].get("trace_id").is_none());
+
+        let is_hex = |value: &Value, len: usize| {
+            let value = value.as_str().unwrap();
+            value.len() == len && value.chars().all(|c| c.is_ascii_hexdigit())
+        };
+        assert!(is_hex(&lines[1]["trace_id"], 32));
+        assert!(is_hex(&lines[1]["span_id"], 16));
+        assert_eq!(lines[1]["trace_flags"], "01");
+        assert_eq!(lines[1]["msg"], "within a span");
+    }
+}
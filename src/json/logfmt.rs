@@ -0,0 +1,283 @@
+use std::fmt::Write;
+
+use serde_json::Value;
+use tracing::{Event, Subscriber};
+use tracing_log::NormalizeEvent;
+use tracing_subscriber::{
+    layer::Context,
+    registry::{LookupSpan, SpanRef},
+};
+
+use crate::json::formatter::{scope_fields, span_fields, span_timings, SPAN_CLOSED_MESSAGE};
+use crate::subscriber::{ContextInfo, EventFormatter};
+
+/// [`EventFormatter`] writing [logfmt](https://brandur.org/logfmt) lines, eg. to be shipped to Loki.
+///
+/// ```text
+/// ts=2024-01-01T10:30:00.000Z level=info msg="Order created" type=my-app country=it environment=production target=my_app::orders span=create_order order_id=42 user_id=1
+/// ```
+///
+/// The fields of the event and its spans follow, sorted by name, then the trace and span ids
+/// when the `traces` feature is enabled. The fields named as the keys written by the formatter
+/// itself, eg. `level`, are skipped.
+#[derive(Default)]
+pub struct LogfmtEventFormatter;
+
+/// Keys written by [`LogfmtEventFormatter`] before the fields.
+const RESERVED_KEYS: [&str; 8] = [
+    "ts",
+    "level",
+    "msg",
+    "type",
+    "country",
+    "environment",
+    "target",
+    "span",
+];
+
+/// Whether a field named `key` would collide with a key written by the formatter itself.
+fn is_reserved_key(key: &str) -> bool {
+    if RESERVED_KEYS.contains(&key) {
+        return true;
+    }
+    #[cfg(any(feature = "traces", feature = "datadog"))]
+    if ["trace_id", "span_id"].contains(&key) {
+        return true;
+    }
+    false
+}
+
+impl EventFormatter for LogfmtEventFormatter {
+    fn format_event<S>(
+        &self,
+        event: &Event<'_>,
+        ctx: Context<'_, S>,
+        info: ContextInfo<'_>,
+    ) -> Result<Vec<u8>, std::io::Error>
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        let normalized_metadata = event.normalized_metadata();
+        let metadata = normalized_metadata
+            .as_ref()
+            .unwrap_or_else(|| event.metadata());
+
+        let mut visitor = info.json_visitor();
+        event.record(&mut visitor);
+
+        let mut line = String::new();
+        let timestamp = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+        write_pair(&mut line, "ts", &timestamp);
+        write_pair(
+            &mut line,
+            "level",
+            &metadata.level().to_string().to_lowercase(),
+        );
+        if let Some(message) = visitor.fields().get("message") {
+            write_pair(&mut line, "msg", &to_text(message));
+        }
+        write_pair(&mut line, "type", info.app_name());
+        write_pair(&mut line, "country", info.country());
+        write_pair(&mut line, "environment", info.environment());
+        write_pair(&mut line, "target", metadata.target());
+        if let Some(span) = ctx.event_span(event) {
+            write_pair(&mut line, "span", span.name());
+        }
+
+        let mut fields = span_fields(event, &ctx);
+        for (key, value) in visitor
+            .fields()
+            .iter()
+            .filter(|(&key, _)| key != "message" && !key.starts_with("log."))
+        {
+            fields.insert(key.to_string(), value.clone());
+        }
+        write_fields(&mut line, fields);
+
+        #[cfg(any(feature = "traces", feature = "datadog"))]
+        if let Some((trace_id, span_id)) = crate::json::formatter::otel_ids(&ctx) {
+            write_pair(&mut line, "trace_id", &trace_id.to_string());
+            write_pair(&mut line, "span_id", &span_id.to_string());
+        }
+
+        Ok(line.into_bytes())
+    }
+
+    fn format_span_close<S>(
+        &self,
+        span: &SpanRef<'_, S>,
+        info: ContextInfo<'_>,
+    ) -> Result<Vec<u8>, std::io::Error>
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        let metadata = span.metadata();
+
+        let mut line = String::new();
+        let timestamp = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+        write_pair(&mut line, "ts", &timestamp);
+        write_pair(
+            &mut line,
+            "level",
+            &metadata.level().to_string().to_lowercase(),
+        );
+        write_pair(&mut line, "msg", SPAN_CLOSED_MESSAGE);
+        write_pair(&mut line, "type", info.app_name());
+        write_pair(&mut line, "country", info.country());
+        write_pair(&mut line, "environment", info.environment());
+        write_pair(&mut line, "target", metadata.target());
+        write_pair(&mut line, "span", span.name());
+        let timings = span_timings(span);
+        let mut fields = scope_fields(span);
+        fields.retain(|key, _| timings.iter().all(|(name, _)| name != key));
+        write_fields(&mut line, fields);
+        for (key, value) in timings {
+            write_pair(&mut line, key, &value.to_string());
+        }
+
+        Ok(line.into_bytes())
+    }
+}
+
+/// Write the fields sorted by name, skipping the reserved ones.
+fn write_fields(line: &mut String, fields: serde_json::Map<String, Value>) {
+    let mut fields: Vec<_> = fields
+        .into_iter()
+        .filter(|(key, _)| !is_reserved_key(key))
+        .collect();
+    fields.sort_by(|(a, _), (b, _)| a.cmp(b));
+    for (key, value) in &fields {
+        write_pair(line, key, &to_text(value));
+    }
+}
+
+fn to_text(value: &Value) -> String {
+    match value {
+        Value::String(string) => string.clone(),
+        value => value.to_string(),
+    }
+}
+
+/// Write `key=value`, quoting the value when it's empty or contains spaces, quotes or `=`.
+fn write_pair(line: &mut String, key: &str, value: &str) {
+    if !line.is_empty() {
+        line.push(' ');
+    }
+    line.push_str(key);
+    line.push('=');
+
+    let needs_quotes = value.is_empty()
+        || value
+            .chars()
+            .any(|c| c.is_whitespace() || c.is_control() || c == '"' || c == '=');
+    if !needs_quotes {
+        line.push_str(value);
+        return;
+    }
+
+    line.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => line.push_str("\\\""),
+            '\\' => line.push_str("\\\\"),
+            '\n' => line.push_str("\\n"),
+            '\r' => line.push_str("\\r"),
+            '\t' => line.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(line, "\\u{:04x}", c as u32);
+            }
+            c => line.push(c),
+        }
+    }
+    line.push('"');
+}
+
+#[cfg(test)]
+mod test {
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use super::*;
+    use crate::json::formatter::PrimaFormattingLayer;
+    use crate::json::storage::PrimaJsonStorage;
+    use crate::json::test::{format, format_span_close, Buffer};
+
+    #[test]
+    fn logfmt_format_test() {
+        let output = format(LogfmtEventFormatter);
+        let (timestamp, rest) = output.split_once(' ').unwrap();
+
+        assert!(timestamp.starts_with("ts="));
+        assert_eq!(
+            rest,
+            "level=warn msg=\"Order created\" type=app country=common environment=dev \
+             target=prima_tracing::json::test span=create_order order_id=42 path=/orders \
+             retry=true user_id=1\n"
+        );
+    }
+
+    #[test]
+    fn logfmt_reserved_keys_test() {
+        let buffer = Buffer::default();
+        let subscriber = tracing_subscriber::Registry::default()
+            .with(PrimaJsonStorage::default())
+            .with(PrimaFormattingLayer::new(
+                "app".to_string(),
+                "common".to_string(),
+                "dev".to_string(),
+                buffer.clone(),
+                LogfmtEventFormatter,
+            ));
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info_span!("request", level = "debug", span = "other", user_id = 1).in_scope(
+                || {
+                    tracing::warn!(
+                        msg = "other",
+                        ts = 0,
+                        target = "other",
+                        "type" = "other",
+                        order_id = 42,
+                        "Order created"
+                    );
+                },
+            );
+        });
+
+        let output = buffer.contents();
+        let (_, rest) = output.split_once(' ').unwrap();
+        assert_eq!(
+            rest,
+            "level=warn msg=\"Order created\" type=app country=common environment=dev \
+             target=prima_tracing::json::logfmt::test span=request order_id=42 user_id=1\n"
+        );
+    }
+
+    #[test]
+    fn write_pair_test() {
+        let mut line = String::new();
+        write_pair(&mut line, "plain", "value");
+        write_pair(&mut line, "empty", "");
+        write_pair(&mut line, "spaces", "two words");
+        write_pair(&mut line, "quotes", "say \"hi\"\n");
+        write_pair(&mut line, "equals", "a=b");
+
+        assert_eq!(
+            line,
+            r#"plain=value empty="" spaces="two words" quotes="say \"hi\"\n" equals="a=b""#
+        );
+    }
+
+    #[test]
+    fn logfmt_span_close_test() {
+        let lines = format_span_close(LogfmtEventFormatter);
+        assert_eq!(lines.len(), 1);
+
+        let (_, rest) = lines[0].split_once(' ').unwrap();
+        assert!(rest.starts_with(
+            "level=info msg=\"span closed\" type=app country=common environment=dev \
+             target=prima_tracing::json::test span=request path=/orders elapsed_ms="
+        ));
+        assert!(rest.contains(" busy_ms="));
+        assert!(rest.contains(" idle_ms="));
+    }
+}
@@ -1,2 +1,97 @@
+pub mod ecs;
 pub mod formatter;
+pub mod logfmt;
+pub mod pretty;
 pub mod storage;
+#[cfg(all(tracing_unstable, feature = "valuable"))]
+mod valuable_json;
+
+#[cfg(test)]
+mod test {
+    use std::io::Write;
+    use std::sync::{Arc, Mutex};
+
+    use tracing_subscriber::{fmt::MakeWriter, layer::SubscriberExt};
+
+    use crate::json::{formatter::PrimaFormattingLayer, storage::PrimaJsonStorage};
+    use crate::EventFormatter;
+
+    #[derive(Clone, Default)]
+    pub(crate) struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Buffer {
+        pub(crate) fn contents(&self) -> String {
+            String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+        }
+    }
+
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl<'a> MakeWriter<'a> for Buffer {
+        type Writer = Self;
+
+        fn make_writer(&'a self) -> Self::Writer {
+            self.clone()
+        }
+    }
+
+    /// Output of `formatter` for a warning emitted within two nested spans.
+    pub(crate) fn format<F: EventFormatter + Send + Sync + 'static>(formatter: F) -> String {
+        let buffer = Buffer::default();
+        let subscriber = tracing_subscriber::Registry::default()
+            .with(PrimaJsonStorage::default())
+            .with(PrimaFormattingLayer::new(
+                "app".to_string(),
+                "common".to_string(),
+                "dev".to_string(),
+                buffer.clone(),
+                formatter,
+            ));
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info_span!("request", path = "/orders").in_scope(|| {
+                tracing::info_span!("create_order", user_id = 1).in_scope(|| {
+                    tracing::warn!(order_id = 42, retry = true, "Order created");
+                });
+            });
+        });
+
+        buffer.contents()
+    }
+
+    /// Lines written by `formatter` when a debug span closes within an info one, with the span
+    /// close events enabled up to `INFO`.
+    pub(crate) fn format_span_close<F: EventFormatter + Send + Sync + 'static>(
+        formatter: F,
+    ) -> Vec<String> {
+        let buffer = Buffer::default();
+        let subscriber = tracing_subscriber::Registry::default()
+            .with(PrimaJsonStorage::default())
+            .with(
+                PrimaFormattingLayer::new(
+                    "app".to_string(),
+                    "common".to_string(),
+                    "dev".to_string(),
+                    buffer.clone(),
+                    formatter,
+                )
+                .with_span_close_events(tracing::Level::INFO),
+            );
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info_span!("request", path = "/orders").in_scope(|| {
+                tracing::debug_span!("query").in_scope(|| {});
+            });
+        });
+
+        buffer.contents().lines().map(String::from).collect()
+    }
+}
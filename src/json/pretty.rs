@@ -0,0 +1,260 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use tracing::{Event, Level, Metadata, Subscriber};
+use tracing_log::NormalizeEvent;
+use tracing_subscriber::{
+    layer::Context,
+    registry::{LookupSpan, SpanRef},
+};
+
+use crate::json::formatter::{span_timings, SPAN_CLOSED_MESSAGE};
+
+use crate::json::storage::PrimaJsonVisitor;
+use crate::subscriber::{ContextInfo, EventFormatter};
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const DIMMED: &str = "\x1b[2m";
+const ITALIC: &str = "\x1b[3m";
+
+/// Human readable [`EventFormatter`], used by default in [`crate::Environment::Dev`]
+/// instead of the JSON lines of [`crate::json::formatter::DefaultEventFormatter`].
+///
+/// ```text
+/// 2024-01-01T10:30:00.000Z  INFO my_app::orders: Order created order_id=42
+///     in handle_request{path=/orders} > create_order{user_id=1}
+///     dd.trace_id=1234 dd.span_id=5678
+/// ```
+pub struct PrettyEventFormatter {
+    ansi: bool,
+}
+
+impl Default for PrettyEventFormatter {
+    fn default() -> Self {
+        Self { ansi: true }
+    }
+}
+
+impl PrettyEventFormatter {
+    /// Whether to colorize the output with ANSI escape codes, enabled by default.
+    pub fn with_ansi(mut self, ansi: bool) -> Self {
+        self.ansi = ansi;
+        self
+    }
+
+    fn paint(&self, line: &mut String, style: &str, text: impl std::fmt::Display) {
+        if self.ansi {
+            let _ = write!(line, "{style}{text}{RESET}");
+        } else {
+            let _ = write!(line, "{text}");
+        }
+    }
+
+    fn level_style(level: &Level) -> &'static str {
+        match *level {
+            Level::ERROR => "\x1b[31m",
+            Level::WARN => "\x1b[33m",
+            Level::INFO => "\x1b[32m",
+            Level::DEBUG => "\x1b[34m",
+            Level::TRACE => "\x1b[35m",
+        }
+    }
+
+    fn write_fields(&self, line: &mut String, fields: &HashMap<&str, serde_json::Value>) {
+        let mut fields: Vec<_> = fields
+            .iter()
+            .filter(|(&key, _)| key != "message" && !key.starts_with("log."))
+            .collect();
+        fields.sort_by_key(|(&key, _)| key);
+
+        for (key, value) in fields {
+            line.push(' ');
+            self.paint(line, ITALIC, key);
+            line.push('=');
+            write_value(line, value);
+        }
+    }
+
+    /// Timestamp, level and target.
+    fn write_header(&self, line: &mut String, metadata: &Metadata<'_>) {
+        let timestamp = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+        self.paint(line, DIMMED, timestamp);
+        line.push(' ');
+        self.paint(
+            line,
+            Self::level_style(metadata.level()),
+            format_args!("{:>5}", metadata.level()),
+        );
+        line.push(' ');
+        self.paint(line, DIMMED, format_args!("{}:", metadata.target()));
+    }
+
+    fn write_message(&self, line: &mut String, message: &serde_json::Value) {
+        line.push(' ');
+        let mut text = String::new();
+        write_value(&mut text, message);
+        self.paint(line, BOLD, text);
+    }
+
+    /// Breadcrumb of `span` and of the spans containing it, with their fields.
+    fn write_scope<S>(&self, line: &mut String, span: &SpanRef<'_, S>)
+    where
+        S: for<'a> LookupSpan<'a>,
+    {
+        line.push_str("\n    in ");
+        for (index, span) in span.scope().from_root().enumerate() {
+            if index > 0 {
+                line.push_str(" > ");
+            }
+            self.paint(line, BOLD, span.name());
+            if let Some(visitor) = span.extensions().get::<PrimaJsonVisitor>() {
+                let mut fields = String::new();
+                self.write_fields(&mut fields, visitor.fields());
+                // Drop the separator before the first field, if any is written
+                if let Some(fields) = fields.strip_prefix(' ') {
+                    line.push('{');
+                    line.push_str(fields);
+                    line.push('}');
+                }
+            }
+        }
+    }
+}
+
+fn write_value(line: &mut String, value: &serde_json::Value) {
+    let _ = match value {
+        serde_json::Value::String(string) => write!(line, "{string}"),
+        value => write!(line, "{value}"),
+    };
+}
+
+impl EventFormatter for PrettyEventFormatter {
+    fn format_event<S>(
+        &self,
+        event: &Event<'_>,
+        ctx: Context<'_, S>,
+        info: ContextInfo<'_>,
+    ) -> Result<Vec<u8>, std::io::Error>
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        let normalized_metadata = event.normalized_metadata();
+        let metadata = normalized_metadata
+            .as_ref()
+            .unwrap_or_else(|| event.metadata());
+
+        let mut visitor = info.json_visitor();
+        event.record(&mut visitor);
+
+        let mut line = String::new();
+        self.write_header(&mut line, metadata);
+        if let Some(message) = visitor.fields().get("message") {
+            self.write_message(&mut line, message);
+        }
+        self.write_fields(&mut line, visitor.fields());
+
+        if let Some(span) = ctx.event_span(event) {
+            self.write_scope(&mut line, &span);
+        }
+
+        #[cfg(feature = "datadog")]
+        if let Some((trace_id, span_id)) = crate::json::formatter::datadog_ids(&ctx) {
+            line.push_str("\n    ");
+            self.paint(
+                &mut line,
+                DIMMED,
+                format_args!("dd.trace_id={trace_id} dd.span_id={span_id}"),
+            );
+        }
+
+        Ok(line.into_bytes())
+    }
+
+    fn format_span_close<S>(
+        &self,
+        span: &SpanRef<'_, S>,
+        _info: ContextInfo<'_>,
+    ) -> Result<Vec<u8>, std::io::Error>
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        let timings: HashMap<&str, serde_json::Value> = span_timings(span)
+            .into_iter()
+            .map(|(key, value)| (key, serde_json::Value::from(value)))
+            .collect();
+
+        let mut line = String::new();
+        self.write_header(&mut line, span.metadata());
+        self.write_message(&mut line, &serde_json::Value::from(SPAN_CLOSED_MESSAGE));
+        self.write_fields(&mut line, &timings);
+        self.write_scope(&mut line, span);
+
+        Ok(line.into_bytes())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use super::*;
+    use crate::json::test::{format, format_span_close};
+
+    #[test]
+    fn pretty_format_test() {
+        let output = format(PrettyEventFormatter::default().with_ansi(false));
+        let (timestamp, rest) = output.split_once(' ').unwrap();
+
+        assert!(chrono::DateTime::parse_from_rfc3339(timestamp).is_ok());
+        assert_eq!(
+            rest,
+            " WARN prima_tracing::json::test: Order created order_id=42 retry=true\n    \
+             in request{path=/orders} > create_order{user_id=1}\n"
+        );
+    }
+
+    #[test]
+    fn pretty_format_span_without_written_fields_test() {
+        let buffer = crate::json::test::Buffer::default();
+        let subscriber = tracing_subscriber::Registry::default()
+            .with(crate::json::storage::PrimaJsonStorage::default())
+            .with(crate::json::formatter::PrimaFormattingLayer::new(
+                "app".to_string(),
+                "common".to_string(),
+                "dev".to_string(),
+                buffer.clone(),
+                PrettyEventFormatter::default().with_ansi(false),
+            ));
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info_span!("request", message = "ignored", log.target = "app").in_scope(
+                || {
+                    tracing::info!("Order created");
+                },
+            );
+        });
+
+        assert!(buffer
+            .contents()
+            .ends_with(": Order created\n    in request\n"));
+    }
+
+    #[test]
+    fn pretty_format_ansi_test() {
+        let output = format(PrettyEventFormatter::default());
+
+        assert!(output.contains("\x1b[33m WARN\x1b[0m"));
+        assert!(output.contains("\x1b[1mOrder created\x1b[0m"));
+    }
+
+    #[test]
+    fn pretty_span_close_test() {
+        let lines = format_span_close(PrettyEventFormatter::default().with_ansi(false));
+        let line = lines.join("\n");
+
+        assert!(line.contains(" INFO prima_tracing::json::test: span closed busy_ms="));
+        assert!(line.contains(" elapsed_ms="));
+        assert!(line.ends_with("\n    in request{path=/orders}"));
+    }
+}
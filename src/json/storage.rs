@@ -1,4 +1,6 @@
 use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use tracing::{
     field::{Field, Visit},
@@ -6,18 +8,97 @@ use tracing::{
 };
 use tracing_subscriber::{layer::Context, Layer};
 
+#[cfg(feature = "redaction")]
+use crate::redaction::Redactor;
+
 #[derive(Default)]
 pub struct PrimaJsonVisitor<'a> {
     fields: HashMap<&'a str, serde_json::Value>,
+    // Merged fields of the parent span, shared by its children, with field propagation
+    inherited: Option<Arc<HashMap<&'a str, serde_json::Value>>>,
+    #[cfg(feature = "redaction")]
+    redactor: Option<Arc<Redactor>>,
+}
+/// Time spent by a span, stored in its extensions by [`PrimaJsonStorage`].
+#[derive(Clone, Copy, Debug)]
+pub struct SpanTiming {
+    created: Instant,
+    // Last time the span was entered or exited
+    last: Instant,
+    busy: Duration,
+    idle: Duration,
+    closed: Option<Instant>,
+}
+
+impl SpanTiming {
+    fn new(now: Instant) -> Self {
+        Self {
+            created: now,
+            last: now,
+            busy: Duration::ZERO,
+            idle: Duration::ZERO,
+            closed: None,
+        }
+    }
+
+    /// Time spent within the span, between entering and exiting it.
+    pub fn busy(&self) -> Duration {
+        self.busy
+    }
+
+    /// Time spent outside of the span since its creation, eg. while its future was not polled.
+    pub fn idle(&self) -> Duration {
+        self.idle
+    }
+
+    /// Time from the creation of the span to its closing, or to now while it's open.
+    pub fn elapsed(&self) -> Duration {
+        self.closed
+            .unwrap_or_else(Instant::now)
+            .saturating_duration_since(self.created)
+    }
 }
+
 /// Tracing layer providing a store for attributes associated to spans.
 /// Inspired (almost a copy-paste) of `JsonStorageLayer` from `tracing-bunyan-formatter`
 #[derive(Default)]
-pub struct PrimaJsonStorage;
+pub struct PrimaJsonStorage {
+    propagate_fields: bool,
+    #[cfg(feature = "redaction")]
+    redactor: Option<Arc<Redactor>>,
+}
+
+impl PrimaJsonStorage {
+    /// Propagate the fields of the spans down to their children, the fields of a child
+    /// overriding the ones of its parent with the same name. The result is available via
+    /// [`PrimaJsonVisitor::merged_fields`], and is used by the bundled formatters to write the
+    /// span fields at root level without walking the spans containing the event.
+    ///
+    /// The children of a span without fields of its own share the merged fields of its parent,
+    /// the others get a copy taken at their creation: fields recorded on a parent after the
+    /// creation of a child are not propagated to it.
+    pub fn with_field_propagation(mut self) -> Self {
+        self.propagate_fields = true;
+        self
+    }
+
+    #[cfg(feature = "redaction")]
+    pub(crate) fn with_redactor(mut self, redactor: Option<Arc<Redactor>>) -> Self {
+        self.redactor = redactor;
+        self
+    }
+
+    fn visitor(&self) -> PrimaJsonVisitor<'static> {
+        let visitor = PrimaJsonVisitor::default();
+        #[cfg(feature = "redaction")]
+        let visitor = visitor.with_redactor(self.redactor.clone());
+        visitor
+    }
+}
 
 /// Build a [`PrimaJsonStorage`] layer
 pub fn layer() -> PrimaJsonStorage {
-    PrimaJsonStorage
+    PrimaJsonStorage::default()
 }
 impl<S> Layer<S> for PrimaJsonStorage
 where
@@ -25,12 +106,26 @@ where
 {
     fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
         let span = ctx.span(id).expect("Span not found, this is a bug");
+        let inherited = self.propagate_fields.then(|| {
+            span.parent()
+                .and_then(|parent| {
+                    let extensions = parent.extensions();
+                    extensions
+                        .get::<PrimaJsonVisitor>()
+                        .map(|visitor| visitor.propagated())
+                })
+                .unwrap_or_default()
+        });
         let mut extensions = span.extensions_mut();
+        extensions.insert(SpanTiming::new(Instant::now()));
 
         if let Some(visitor) = extensions.get_mut::<PrimaJsonVisitor>() {
             attrs.record(visitor);
         } else {
-            let mut visitor = PrimaJsonVisitor::default();
+            let mut visitor = PrimaJsonVisitor {
+                inherited,
+                ..self.visitor()
+            };
             attrs.record(&mut visitor);
             extensions.insert(visitor);
         }
@@ -43,55 +138,284 @@ where
         if let Some(visitor) = extensions.get_mut::<PrimaJsonVisitor>() {
             values.record(visitor);
         } else {
-            let mut visitor = PrimaJsonVisitor::default();
+            let mut visitor = self.visitor();
             values.record(&mut visitor);
             extensions.insert(visitor);
         }
     }
+
+    fn on_enter(&self, id: &span::Id, ctx: Context<'_, S>) {
+        let span = ctx.span(id).expect("Span not found, this is a bug");
+        let mut extensions = span.extensions_mut();
+        if let Some(timing) = extensions.get_mut::<SpanTiming>() {
+            let now = Instant::now();
+            timing.idle += now.saturating_duration_since(timing.last);
+            timing.last = now;
+        }
+    }
+
+    fn on_exit(&self, id: &span::Id, ctx: Context<'_, S>) {
+        let span = ctx.span(id).expect("Span not found, this is a bug");
+        let mut extensions = span.extensions_mut();
+        if let Some(timing) = extensions.get_mut::<SpanTiming>() {
+            let now = Instant::now();
+            timing.busy += now.saturating_duration_since(timing.last);
+            timing.last = now;
+        }
+    }
+
+    fn on_close(&self, id: span::Id, ctx: Context<'_, S>) {
+        let span = ctx.span(&id).expect("Span not found, this is a bug");
+        let mut extensions = span.extensions_mut();
+        if let Some(timing) = extensions.get_mut::<SpanTiming>() {
+            let now = Instant::now();
+            timing.idle += now.saturating_duration_since(timing.last);
+            timing.last = now;
+            timing.closed = Some(now);
+        }
+    }
 }
 
 impl PrimaJsonVisitor<'_> {
+    /// Fields recorded on the span or event itself.
     pub fn fields(&self) -> &HashMap<&str, serde_json::Value> {
         &self.fields
     }
+
+    /// Fields of the span merged with the ones propagated from its parents, see
+    /// [`PrimaJsonStorage::with_field_propagation`]. Without propagation they are the
+    /// same as [`PrimaJsonVisitor::fields`].
+    pub fn merged_fields(&self) -> HashMap<&str, serde_json::Value> {
+        self.propagated().as_ref().clone()
+    }
+
+    /// Whether the span has been stored with [`PrimaJsonStorage::with_field_propagation`].
+    pub(crate) fn is_propagated(&self) -> bool {
+        self.inherited.is_some()
+    }
 }
 
 impl<'a> PrimaJsonVisitor<'a> {
+    /// Merged fields passed down to the children of the span, without copying the ones of
+    /// the parent if the span has no fields of its own.
+    fn propagated(&self) -> Arc<HashMap<&'a str, serde_json::Value>> {
+        let inherited = self.inherited.clone().unwrap_or_default();
+        if self.fields.is_empty() {
+            return inherited;
+        }
+        let mut merged = inherited.as_ref().clone();
+        merged.extend(self.fields.iter().map(|(key, value)| (*key, value.clone())));
+        Arc::new(merged)
+    }
+
     pub fn get<T: FromValue<'a>>(&'a self, field: &'a str) -> Option<T> {
         self.fields.get(field).and_then(T::from_value)
     }
+
+    /// Like [`PrimaJsonVisitor::get`], but reporting whether the field is missing or has a
+    /// different type. A missing field is `None` for `Option`s.
+    pub fn get_or_err<T: FromValue<'a>>(&'a self, field: &'a str) -> Result<T, FieldError> {
+        match self.fields.get(field) {
+            Some(value) => T::from_value(value).ok_or_else(|| FieldError::TypeMismatch {
+                field: field.to_string(),
+                expected: std::any::type_name::<T>(),
+                found: json_type(value),
+            }),
+            None => T::missing().ok_or_else(|| FieldError::Missing(field.to_string())),
+        }
+    }
+}
+
+/// Error returned by [`PrimaJsonVisitor::get_or_err`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldError {
+    /// The field has not been recorded.
+    Missing(String),
+    /// The value of the field cannot be converted to the requested type.
+    TypeMismatch {
+        field: String,
+        expected: &'static str,
+        found: &'static str,
+    },
+}
+
+impl std::error::Error for FieldError {}
+
+impl std::fmt::Display for FieldError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Missing(field) => write!(f, "Field {field} is missing"),
+            Self::TypeMismatch {
+                field,
+                expected,
+                found,
+            } => write!(f, "Field {field} is a {found}, expected {expected}"),
+        }
+    }
+}
+
+fn json_type(value: &serde_json::Value) -> &'static str {
+    match value {
+        serde_json::Value::Null => "null",
+        serde_json::Value::Bool(_) => "boolean",
+        serde_json::Value::Number(_) => "number",
+        serde_json::Value::String(_) => "string",
+        serde_json::Value::Array(_) => "array",
+        serde_json::Value::Object(_) => "object",
+    }
+}
+
+impl PrimaJsonVisitor<'_> {
+    #[cfg(feature = "redaction")]
+    pub(crate) fn with_redactor(mut self, redactor: Option<Arc<Redactor>>) -> Self {
+        self.redactor = redactor;
+        self
+    }
+
+    #[cfg_attr(not(feature = "redaction"), allow(unused_mut))]
+    fn insert(&mut self, field: &Field, mut value: serde_json::Value) {
+        #[cfg(feature = "redaction")]
+        if let Some(redactor) = &self.redactor {
+            redactor.redact_json(field.name(), &mut value);
+        }
+        self.fields.insert(field.name(), value);
+    }
 }
 
 impl Visit for PrimaJsonVisitor<'_> {
     fn record_i64(&mut self, field: &Field, value: i64) {
-        self.fields
-            .insert(field.name(), serde_json::Value::from(value));
+        self.insert(field, serde_json::Value::from(value));
     }
 
     fn record_u64(&mut self, field: &Field, value: u64) {
-        self.fields
-            .insert(field.name(), serde_json::Value::from(value));
+        self.insert(field, serde_json::Value::from(value));
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.insert(field, f64_value(value));
+    }
+
+    fn record_i128(&mut self, field: &Field, value: i128) {
+        self.insert(field, i128_value(value));
+    }
+
+    fn record_u128(&mut self, field: &Field, value: u128) {
+        self.insert(field, u128_value(value));
     }
 
     fn record_bool(&mut self, field: &Field, value: bool) {
-        self.fields
-            .insert(field.name(), serde_json::Value::from(value));
+        self.insert(field, serde_json::Value::from(value));
     }
 
     fn record_str(&mut self, field: &Field, value: &str) {
-        self.fields
-            .insert(field.name(), serde_json::Value::from(value));
+        self.insert(field, serde_json::Value::from(value));
+    }
+
+    fn record_bytes(&mut self, field: &Field, value: &[u8]) {
+        self.insert(field, serde_json::Value::from(value));
     }
+
+    fn record_error(&mut self, field: &Field, value: &(dyn std::error::Error + 'static)) {
+        self.insert(field, error_value(value));
+    }
+
+    #[cfg(all(tracing_unstable, feature = "valuable"))]
+    fn record_value(&mut self, field: &Field, value: valuable::Value<'_>) {
+        self.insert(field, crate::json::valuable_json::to_json(value));
+    }
+
     fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
-        self.fields
-            .insert(field.name(), serde_json::Value::from(format!("{value:?}")));
+        self.insert(field, serde_json::Value::from(format!("{value:?}")));
+    }
+}
+
+/// NaN and infinite numbers are not valid JSON, they are written as strings.
+pub(crate) fn f64_value(value: f64) -> serde_json::Value {
+    serde_json::Number::from_f64(value).map_or_else(
+        || serde_json::Value::from(value.to_string()),
+        serde_json::Value::Number,
+    )
+}
+
+/// Numbers out of the 64 bit range are written as strings, so that they don't lose precision.
+pub(crate) fn i128_value(value: i128) -> serde_json::Value {
+    match (i64::try_from(value), u64::try_from(value)) {
+        (Ok(value), _) => serde_json::Value::from(value),
+        (_, Ok(value)) => serde_json::Value::from(value),
+        _ => serde_json::Value::from(value.to_string()),
+    }
+}
+
+/// Numbers out of the 64 bit range are written as strings, so that they don't lose precision.
+pub(crate) fn u128_value(value: u128) -> serde_json::Value {
+    u64::try_from(value).map_or_else(
+        |_| serde_json::Value::from(value.to_string()),
+        serde_json::Value::from,
+    )
+}
+
+/// Errors are written as an object with their `message`, the messages of the chain of their
+/// `sources` and, for IO errors, their [`std::io::ErrorKind`] as `kind`.
+pub(crate) fn error_value(error: &(dyn std::error::Error + 'static)) -> serde_json::Value {
+    let sources: Vec<String> = std::iter::successors(error.source(), |source| source.source())
+        .map(ToString::to_string)
+        .collect();
+
+    let mut value = serde_json::json!({
+        "message": error.to_string(),
+        "sources": sources,
+    });
+    // Not `kind`, which would clash with the `error.kind` recorded by `report_error!` once
+    // flattened, eg. by Datadog
+    if let Some(io_error) = error.downcast_ref::<std::io::Error>() {
+        value["io_kind"] = format!("{:?}", io_error.kind()).into();
     }
+    value
 }
 
+/// Conversion from the JSON value of a field, `None` when the value has a different type or
+/// doesn't fit in `Self`.
 pub trait FromValue<'a> {
     fn from_value(value: &'a serde_json::Value) -> Option<Self>
     where
         Self: Sized;
+
+    /// Value of a missing field, `None` unless `Self` is optional.
+    fn missing() -> Option<Self>
+    where
+        Self: Sized,
+    {
+        None
+    }
+}
+
+impl<'a> FromValue<'a> for &'a serde_json::Value {
+    fn from_value(value: &'a serde_json::Value) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        Some(value)
+    }
+}
+
+/// `null` and missing fields are `None`.
+impl<'a, T: FromValue<'a>> FromValue<'a> for Option<T> {
+    fn from_value(value: &'a serde_json::Value) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        match value {
+            serde_json::Value::Null => Some(None),
+            value => T::from_value(value).map(Some),
+        }
+    }
+
+    fn missing() -> Option<Self>
+    where
+        Self: Sized,
+    {
+        Some(None)
+    }
 }
 
 impl<'a> FromValue<'a> for &'a str {
@@ -112,8 +436,341 @@ impl<'a> FromValue<'a> for u32 {
         Self: Sized,
     {
         match value {
-            serde_json::Value::Number(number) => number.as_u64().map(|number| number as u32),
+            serde_json::Value::Number(number) => number
+                .as_u64()
+                .and_then(|number| u32::try_from(number).ok()),
             _ => None,
         }
     }
 }
+
+impl<'a> FromValue<'a> for String {
+    fn from_value(value: &'a serde_json::Value) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        value.as_str().map(ToString::to_string)
+    }
+}
+
+impl<'a> FromValue<'a> for i64 {
+    fn from_value(value: &'a serde_json::Value) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        value.as_i64()
+    }
+}
+
+impl<'a> FromValue<'a> for u64 {
+    fn from_value(value: &'a serde_json::Value) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        value.as_u64()
+    }
+}
+
+/// Any number, integers are converted to the closest `f64`.
+impl<'a> FromValue<'a> for f64 {
+    fn from_value(value: &'a serde_json::Value) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        value.as_f64()
+    }
+}
+
+impl<'a> FromValue<'a> for bool {
+    fn from_value(value: &'a serde_json::Value) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        value.as_bool()
+    }
+}
+
+/// RFC 3339 timestamps, as well as the `Display` format of `chrono`, eg. `2024-01-01 10:30:00 UTC`.
+impl<'a> FromValue<'a> for chrono::DateTime<chrono::FixedOffset> {
+    fn from_value(value: &'a serde_json::Value) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        let value = value.as_str()?;
+        chrono::DateTime::parse_from_rfc3339(value)
+            .or_else(|_| value.parse())
+            .ok()
+    }
+}
+
+/// Same formats as `DateTime<FixedOffset>`, converted to UTC.
+impl<'a> FromValue<'a> for chrono::DateTime<chrono::Utc> {
+    fn from_value(value: &'a serde_json::Value) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        chrono::DateTime::<chrono::FixedOffset>::from_value(value)
+            .map(|timestamp| timestamp.with_timezone(&chrono::Utc))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::TimeZone;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use super::*;
+
+    #[derive(Debug)]
+    struct ConfigError(std::io::Error);
+
+    impl std::fmt::Display for ConfigError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "Failed to read the configuration")
+        }
+    }
+
+    impl std::error::Error for ConfigError {
+        fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+            Some(&self.0)
+        }
+    }
+
+    /// Fields recorded by the visitor for an event emitted by `emit`.
+    fn record(emit: impl FnOnce()) -> HashMap<String, serde_json::Value> {
+        struct Recorder(std::sync::Arc<std::sync::Mutex<HashMap<String, serde_json::Value>>>);
+
+        impl<S: Subscriber> Layer<S> for Recorder {
+            fn on_event(&self, event: &tracing::Event<'_>, _ctx: Context<'_, S>) {
+                let mut visitor = PrimaJsonVisitor::default();
+                event.record(&mut visitor);
+                let mut fields = self.0.lock().unwrap();
+                for (key, value) in visitor.fields() {
+                    fields.insert(key.to_string(), value.clone());
+                }
+            }
+        }
+
+        let fields = std::sync::Arc::default();
+        let subscriber =
+            tracing_subscriber::Registry::default().with(Recorder(std::sync::Arc::clone(&fields)));
+        tracing::subscriber::with_default(subscriber, emit);
+        let fields = fields.lock().unwrap().clone();
+        fields
+    }
+
+    #[test]
+    fn numbers_test() {
+        let fields = record(|| {
+            tracing::info!(
+                ratio = 0.25,
+                not_a_number = f64::NAN,
+                small = 7_i128,
+                above_i64 = i64::MAX as i128 + 1,
+                max_u64 = u64::MAX as i128,
+                above_u64 = u64::MAX as i128 + 1,
+                huge = u128::MAX,
+                negative = i128::MIN,
+            )
+        });
+
+        assert_eq!(fields["ratio"], 0.25);
+        assert_eq!(fields["not_a_number"], "NaN");
+        assert_eq!(fields["small"], 7);
+        assert_eq!(fields["above_i64"], i64::MAX as u64 + 1);
+        assert_eq!(fields["max_u64"], u64::MAX);
+        assert_eq!(fields["above_u64"], (u64::MAX as i128 + 1).to_string());
+        assert_eq!(fields["huge"], u128::MAX.to_string());
+        assert_eq!(fields["negative"], i128::MIN.to_string());
+    }
+
+    #[test]
+    fn error_test() {
+        let error = ConfigError(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "config.toml not found",
+        ));
+        let parse_error = "x".parse::<u32>().unwrap_err();
+        let fields = record(|| {
+            tracing::error!(
+                error = &error as &dyn std::error::Error,
+                parse_error = &parse_error as &dyn std::error::Error,
+                io_error = &error.0 as &dyn std::error::Error,
+            )
+        });
+
+        assert_eq!(
+            fields["error"],
+            serde_json::json!({
+                "message": "Failed to read the configuration",
+                "sources": ["config.toml not found"],
+            })
+        );
+        assert!(fields["parse_error"].get("io_kind").is_none());
+        assert_eq!(fields["io_error"]["io_kind"], "NotFound");
+        assert_eq!(fields["io_error"]["sources"], serde_json::json!([]));
+    }
+
+    fn visitor() -> PrimaJsonVisitor<'static> {
+        PrimaJsonVisitor {
+            fields: HashMap::from([
+                ("name", serde_json::json!("order")),
+                ("id", serde_json::json!(42)),
+                ("negative", serde_json::json!(-1)),
+                ("huge", serde_json::json!(u64::MAX)),
+                ("ratio", serde_json::json!(0.5)),
+                ("retry", serde_json::json!(true)),
+                ("none", serde_json::Value::Null),
+                (
+                    "created_at",
+                    serde_json::json!(chrono::Utc
+                        .with_ymd_and_hms(2024, 1, 1, 10, 30, 0)
+                        .unwrap()
+                        .to_string()),
+                ),
+                ("updated_at", serde_json::json!("2024-01-01T12:30:00+02:00")),
+            ]),
+            ..PrimaJsonVisitor::default()
+        }
+    }
+
+    #[test]
+    fn get_test() {
+        use chrono::{DateTime, FixedOffset, Utc};
+
+        let visitor = visitor();
+        let created_at = Utc.with_ymd_and_hms(2024, 1, 1, 10, 30, 0).unwrap();
+
+        assert_eq!(visitor.get::<&str>("name"), Some("order"));
+        assert_eq!(visitor.get::<String>("name"), Some("order".to_string()));
+        assert_eq!(visitor.get::<u32>("id"), Some(42));
+        assert_eq!(visitor.get::<u32>("huge"), None);
+        assert_eq!(visitor.get::<u64>("huge"), Some(u64::MAX));
+        assert_eq!(visitor.get::<u64>("negative"), None);
+        assert_eq!(visitor.get::<i64>("negative"), Some(-1));
+        assert_eq!(visitor.get::<i64>("huge"), None);
+        assert_eq!(visitor.get::<f64>("ratio"), Some(0.5));
+        assert_eq!(visitor.get::<f64>("id"), Some(42.0));
+        assert_eq!(visitor.get::<bool>("retry"), Some(true));
+        assert_eq!(
+            visitor.get::<&serde_json::Value>("id"),
+            Some(&serde_json::json!(42))
+        );
+        assert_eq!(visitor.get::<Option<u32>>("none"), Some(None));
+        assert_eq!(visitor.get::<Option<u32>>("id"), Some(Some(42)));
+        assert_eq!(visitor.get::<DateTime<Utc>>("created_at"), Some(created_at));
+        assert_eq!(visitor.get::<DateTime<Utc>>("updated_at"), Some(created_at));
+        assert_eq!(
+            visitor
+                .get::<DateTime<FixedOffset>>("updated_at")
+                .map(|timestamp| timestamp.offset().local_minus_utc()),
+            Some(7200)
+        );
+    }
+
+    #[test]
+    fn get_or_err_test() {
+        let visitor = visitor();
+
+        assert_eq!(visitor.get_or_err::<u64>("id"), Ok(42));
+        assert_eq!(visitor.get_or_err::<Option<u64>>("missing"), Ok(None));
+        assert_eq!(
+            visitor.get_or_err::<u64>("missing"),
+            Err(FieldError::Missing("missing".to_string()))
+        );
+
+        let error = visitor.get_or_err::<bool>("name").unwrap_err();
+        assert_eq!(
+            error,
+            FieldError::TypeMismatch {
+                field: "name".to_string(),
+                expected: "bool",
+                found: "string",
+            }
+        );
+        assert_eq!(error.to_string(), "Field name is a string, expected bool");
+    }
+
+    /// Merged fields of the innermost of three nested spans.
+    fn merged_fields(storage: PrimaJsonStorage) -> HashMap<String, serde_json::Value> {
+        struct Recorder(std::sync::Arc<std::sync::Mutex<HashMap<String, serde_json::Value>>>);
+
+        impl<S> Layer<S> for Recorder
+        where
+            S: Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>,
+        {
+            fn on_event(&self, event: &tracing::Event<'_>, ctx: Context<'_, S>) {
+                let span = ctx.event_span(event).unwrap();
+                let extensions = span.extensions();
+                let visitor = extensions.get::<PrimaJsonVisitor>().unwrap();
+                let mut fields = self.0.lock().unwrap();
+                for (key, value) in visitor.merged_fields() {
+                    fields.insert(key.to_string(), value);
+                }
+            }
+        }
+
+        let fields = std::sync::Arc::default();
+        let subscriber = tracing_subscriber::Registry::default()
+            .with(storage)
+            .with(Recorder(std::sync::Arc::clone(&fields)));
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info_span!("request", path = "/orders", user_id = 1).in_scope(|| {
+                tracing::info_span!("create_order", user_id = 2).in_scope(|| {
+                    tracing::info_span!("save", table = "orders")
+                        .in_scope(|| tracing::info!("saved"));
+                });
+            });
+        });
+        let fields = fields.lock().unwrap().clone();
+        fields
+    }
+
+    #[test]
+    fn field_propagation_test() {
+        let fields = merged_fields(PrimaJsonStorage::default().with_field_propagation());
+
+        assert_eq!(fields.len(), 3);
+        assert_eq!(fields["path"], "/orders");
+        assert_eq!(fields["user_id"], 2);
+        assert_eq!(fields["table"], "orders");
+    }
+
+    #[test]
+    fn span_timing_test() {
+        struct Recorder(std::sync::Arc<std::sync::Mutex<Option<SpanTiming>>>);
+
+        impl<S> Layer<S> for Recorder
+        where
+            S: Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>,
+        {
+            fn on_close(&self, id: span::Id, ctx: Context<'_, S>) {
+                let span = ctx.span(&id).unwrap();
+                *self.0.lock().unwrap() = span.extensions().get::<SpanTiming>().copied();
+            }
+        }
+
+        let timing = std::sync::Arc::default();
+        let subscriber = tracing_subscriber::Registry::default()
+            .with(PrimaJsonStorage::default())
+            .with(Recorder(std::sync::Arc::clone(&timing)));
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("request");
+            std::thread::sleep(Duration::from_millis(20));
+            span.in_scope(|| std::thread::sleep(Duration::from_millis(30)));
+        });
+
+        let timing = timing.lock().unwrap().unwrap();
+        assert!(timing.idle() >= Duration::from_millis(20));
+        assert!(timing.busy() >= Duration::from_millis(30));
+        assert!(timing.elapsed() >= timing.busy() + timing.idle());
+    }
+
+    #[test]
+    fn without_field_propagation_test() {
+        let fields = merged_fields(PrimaJsonStorage::default());
+
+        assert_eq!(fields.len(), 1);
+        assert_eq!(fields["table"], "orders");
+    }
+}
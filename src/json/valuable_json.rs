@@ -0,0 +1,191 @@
+use serde_json::{Map, Value as Json};
+use valuable::{Fields, NamedValues, Value, Visit};
+
+use crate::json::storage::{error_value, f64_value, i128_value, u128_value};
+
+/// Convert a `valuable` value into JSON: structs and maps become objects, lists and tuples
+/// arrays and enum variants `{"Variant": fields}`, or just the variant name without fields.
+pub(crate) fn to_json(value: Value<'_>) -> Json {
+    match value {
+        Value::Bool(value) => Json::from(value),
+        Value::Char(value) => Json::from(value.to_string()),
+        Value::F32(value) => f64_value(value.into()),
+        Value::F64(value) => f64_value(value),
+        Value::I8(value) => Json::from(value),
+        Value::I16(value) => Json::from(value),
+        Value::I32(value) => Json::from(value),
+        Value::I64(value) => Json::from(value),
+        Value::I128(value) => i128_value(value),
+        Value::Isize(value) => Json::from(value),
+        Value::String(value) => Json::from(value),
+        Value::U8(value) => Json::from(value),
+        Value::U16(value) => Json::from(value),
+        Value::U32(value) => Json::from(value),
+        Value::U64(value) => Json::from(value),
+        Value::U128(value) => u128_value(value),
+        Value::Usize(value) => Json::from(value),
+        Value::Path(value) => Json::from(value.display().to_string()),
+        Value::Error(value) => error_value(value),
+        Value::Listable(value) => {
+            let mut collector = Collector::list();
+            value.visit(&mut collector);
+            collector.into_json()
+        }
+        Value::Tuplable(value) => {
+            let mut collector = Collector::list();
+            value.visit(&mut collector);
+            collector.into_json()
+        }
+        Value::Mappable(value) => {
+            let mut collector = Collector::object();
+            value.visit(&mut collector);
+            collector.into_json()
+        }
+        Value::Structable(value) => {
+            let mut collector = Collector::for_fields(value.definition().fields());
+            value.visit(&mut collector);
+            collector.into_json()
+        }
+        Value::Enumerable(value) => {
+            let variant = value.variant();
+            if matches!(variant.fields(), Fields::Unnamed(0)) {
+                return Json::from(variant.name());
+            }
+            let mut collector = Collector::for_fields(variant.fields());
+            value.visit(&mut collector);
+
+            let mut object = Map::new();
+            object.insert(variant.name().to_string(), collector.into_json());
+            Json::Object(object)
+        }
+        _ => Json::Null,
+    }
+}
+
+enum Collector {
+    List(Vec<Json>),
+    Object(Map<String, Json>),
+}
+
+impl Collector {
+    fn list() -> Self {
+        Self::List(Vec::new())
+    }
+
+    fn object() -> Self {
+        Self::Object(Map::new())
+    }
+
+    fn for_fields(fields: &Fields<'_>) -> Self {
+        match fields {
+            Fields::Named(_) => Self::object(),
+            Fields::Unnamed(_) => Self::list(),
+        }
+    }
+
+    fn into_json(self) -> Json {
+        match self {
+            Self::List(items) => Json::Array(items),
+            Self::Object(entries) => Json::Object(entries),
+        }
+    }
+
+    fn insert(&mut self, key: String, value: Json) {
+        match self {
+            Self::List(items) => items.push(value),
+            Self::Object(entries) => {
+                entries.insert(key, value);
+            }
+        }
+    }
+}
+
+impl Visit for Collector {
+    fn visit_value(&mut self, value: Value<'_>) {
+        self.insert(String::new(), to_json(value));
+    }
+
+    fn visit_named_fields(&mut self, named_values: &NamedValues<'_>) {
+        for (field, value) in named_values {
+            self.insert(field.name().to_string(), to_json(*value));
+        }
+    }
+
+    fn visit_unnamed_fields(&mut self, values: &[Value<'_>]) {
+        for value in values {
+            self.insert(String::new(), to_json(*value));
+        }
+    }
+
+    fn visit_entry(&mut self, key: Value<'_>, value: Value<'_>) {
+        // JSON keys are strings, other keys are written as their JSON representation
+        let key = match to_json(key) {
+            Json::String(key) => key,
+            key => key.to_string(),
+        };
+        self.insert(key, to_json(value));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+
+    use valuable::{NamedField, StructDef, Structable, Valuable};
+
+    use super::*;
+
+    struct Order {
+        id: u64,
+        items: Vec<(&'static str, f64)>,
+        tags: BTreeMap<String, bool>,
+    }
+
+    static ORDER_FIELDS: &[NamedField<'static>] = &[
+        NamedField::new("id"),
+        NamedField::new("items"),
+        NamedField::new("tags"),
+    ];
+
+    impl Valuable for Order {
+        fn as_value(&self) -> Value<'_> {
+            Value::Structable(self)
+        }
+
+        fn visit(&self, visit: &mut dyn Visit) {
+            visit.visit_named_fields(&NamedValues::new(
+                ORDER_FIELDS,
+                &[
+                    self.id.as_value(),
+                    self.items.as_value(),
+                    self.tags.as_value(),
+                ],
+            ));
+        }
+    }
+
+    impl Structable for Order {
+        fn definition(&self) -> StructDef<'_> {
+            StructDef::new_static("Order", Fields::Named(ORDER_FIELDS))
+        }
+    }
+
+    #[test]
+    fn to_json_test() {
+        let order = Order {
+            id: 42,
+            items: vec![("book", 9.5)],
+            tags: BTreeMap::from([("gift".to_string(), true)]),
+        };
+
+        assert_eq!(
+            to_json(order.as_value()),
+            serde_json::json!({
+                "id": 42,
+                "items": [["book", 9.5]],
+                "tags": {"gift": true},
+            })
+        );
+        assert_eq!(to_json(u128::MAX.as_value()), u128::MAX.to_string());
+    }
+}
@@ -23,7 +23,15 @@
 pub mod macros;
 
 mod config;
+mod error;
+mod filter;
+#[cfg(all(
+    feature = "redaction",
+    any(feature = "json-logger", feature = "traces")
+))]
+mod redaction;
 mod subscriber;
+mod writer;
 
 #[cfg(feature = "async-graphql")]
 pub mod async_graphql;
@@ -31,17 +39,28 @@ pub mod async_graphql;
 pub mod json;
 #[cfg(feature = "traces")]
 pub mod layer;
+#[cfg(feature = "logs")]
+pub mod logs;
+#[cfg(feature = "metrics")]
+pub mod metrics;
 #[cfg(feature = "traces")]
 pub mod resources;
 #[cfg(feature = "traces")]
 pub mod telemetry;
 
 pub use crate::config::{
-    builder, Country, Environment, EnvironmentParseError, SubscriberConfig, SubscriberConfigBuilder,
+    builder, BatchConfig, Country, EnvVarError, Environment, EnvironmentParseError, FilterConfig,
+    FromEnvError, NonBlockingConfig, OverflowPolicy, RedactionConfig, RedactionPattern,
+    RollingFileConfig, Rotation, Sampling, SubscriberConfig, SubscriberConfigBuilder,
+    TelemetryConfig, TelemetryProtocol, TelemetryProtocolParseError, TlsConfig,
 };
+pub use crate::error::Error;
+pub use crate::filter::FilterHandle;
 pub use crate::subscriber::{
-    configure_subscriber, init_subscriber, ContextInfo, EventFormatter, Tracing, Uninstall,
+    configure_subscriber, init_subscriber, try_configure_subscriber, try_init_subscriber,
+    ContextInfo, EventFormatter, SubscriberHandles, Tracing, Uninstall,
 };
+pub use crate::writer::{NonBlockingWriter, RollingFileWriter};
 pub use tracing;
 
 /// Create a tracing error event, casting the error to &dyn [std::error::Error] for [layer::ErrorLayer],
@@ -0,0 +1,172 @@
+use once_cell::sync::Lazy;
+use opentelemetry::logs::{Logger, LoggerProvider};
+use opentelemetry_appender_tracing::layer::OpenTelemetryTracingBridge;
+use opentelemetry_otlp::LogExporter;
+use opentelemetry_sdk::logs::SdkLoggerProvider;
+#[cfg(feature = "redaction")]
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+use tracing::Subscriber;
+use tracing_subscriber::{filter::FilterFn, registry::LookupSpan, Layer};
+
+#[cfg(feature = "redaction")]
+use crate::redaction::{RedactingLoggerProvider, Redactor};
+use crate::telemetry::{build_exporter, run_with_timeout};
+use crate::{Error, SubscriberConfig};
+
+const LOGS_PATH: &str = "/v1/logs";
+
+// Events emitted by the exporter itself must not be exported again, otherwise they would loop forever.
+// The OpenTelemetry crates log with their package name as target, the others with their module path.
+const EXPORTER_TARGETS: [&str; 12] = [
+    "opentelemetry",
+    "opentelemetry_sdk",
+    "opentelemetry-otlp",
+    "opentelemetry_otlp",
+    "opentelemetry-http",
+    "opentelemetry_http",
+    "opentelemetry-appender-tracing",
+    "hyper",
+    "hyper_util",
+    "h2",
+    "reqwest",
+    "tonic",
+];
+
+/// Whether `target` is one of the [`EXPORTER_TARGETS`] crates or one of their modules.
+fn is_exporter_target(target: &str) -> bool {
+    EXPORTER_TARGETS.iter().any(|prefix| {
+        target
+            .strip_prefix(prefix)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+    })
+}
+
+/// Configure the OpenTelemetry logger provider, panicking on failure.
+/// See [`try_configure`] for the fallible version.
+pub fn configure<T>(config: &SubscriberConfig<T>) -> SdkLoggerProvider {
+    try_configure(config).unwrap_or_else(|err| panic!("{err}"))
+}
+
+/// Configure the OpenTelemetry logger provider exporting logs via OTLP.
+/// It shares the resource attributes of the tracer provider configured by [`crate::telemetry`].
+pub fn try_configure<T>(config: &SubscriberConfig<T>) -> Result<SdkLoggerProvider, Error> {
+    let telemetry = config
+        .telemetry
+        .as_ref()
+        .ok_or(Error::MissingTelemetryConfig)?;
+
+    let otlp_exporter = build_exporter!(LogExporter, config.env, telemetry, LOGS_PATH)?;
+
+    let logger_provider = SdkLoggerProvider::builder()
+        .with_batch_exporter(otlp_exporter)
+        .with_resource(crate::telemetry::resource(config, telemetry))
+        .build();
+
+    set_logger_provider(logger_provider.clone());
+
+    Ok(logger_provider)
+}
+
+/// Build the layer bridging [`tracing::Event`]s to the OpenTelemetry logs pipeline
+pub fn layer<S>(logger_provider: &SdkLoggerProvider) -> impl Layer<S> + Send + Sync
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    bridge(logger_provider)
+}
+
+/// Like [`layer`], redacting the body and the attributes of the log records with `redactor`.
+#[cfg(feature = "redaction")]
+pub(crate) fn redacting_layer<S>(
+    logger_provider: &SdkLoggerProvider,
+    redactor: Option<Arc<Redactor>>,
+) -> impl Layer<S> + Send + Sync
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    let (layer, redacting_layer) = match redactor {
+        Some(redactor) => {
+            let provider = RedactingLoggerProvider::new(logger_provider.clone(), redactor);
+            (None, Some(bridge(&provider)))
+        }
+        None => (Some(bridge(logger_provider)), None),
+    };
+    Layer::and_then(layer, redacting_layer)
+}
+
+fn bridge<S, P, L>(logger_provider: &P) -> impl Layer<S> + Send + Sync
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    P: LoggerProvider<Logger = L> + Send + Sync + 'static,
+    L: Logger + Send + Sync + 'static,
+{
+    OpenTelemetryTracingBridge::<P, L>::new(logger_provider).with_filter(FilterFn::new(
+        |metadata| !is_exporter_target(metadata.target()),
+    ))
+}
+
+static LOGGER_PROVIDER: Lazy<Mutex<Option<SdkLoggerProvider>>> = Lazy::new(Default::default);
+
+fn set_logger_provider(new_provider: SdkLoggerProvider) {
+    let mut logger_provider = LOGGER_PROVIDER
+        .lock()
+        .expect("OpenTelemetry logger provider mutex poisoned");
+    logger_provider.replace(new_provider);
+}
+
+pub(crate) fn force_flush_logger_provider(timeout: Duration) -> Result<(), Error> {
+    let Some(logger_provider) = LOGGER_PROVIDER
+        .lock()
+        .expect("OpenTelemetry logger provider mutex poisoned")
+        .clone()
+    else {
+        return Ok(());
+    };
+
+    run_with_timeout(timeout, move || logger_provider.force_flush()).map_err(|source| {
+        Error::Flush {
+            provider: "logger",
+            source,
+        }
+    })
+}
+
+/// Shut down the logger provider, doing nothing if it's missing or already shut down.
+pub(crate) fn shutdown_logger_provider(timeout: Duration) -> Result<(), Error> {
+    let Some(logger_provider) = LOGGER_PROVIDER
+        .lock()
+        .expect("OpenTelemetry logger provider mutex poisoned")
+        .take()
+    else {
+        return Ok(());
+    };
+
+    run_with_timeout(timeout, move || {
+        logger_provider.shutdown_with_timeout(timeout)
+    })
+    .map_err(|source| Error::Shutdown {
+        provider: "logger",
+        source,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn is_exporter_target_test() {
+        assert!(is_exporter_target("opentelemetry_sdk"));
+        assert!(is_exporter_target("opentelemetry-otlp"));
+        assert!(is_exporter_target("hyper"));
+        assert!(is_exporter_target("hyper_util::client::legacy"));
+        assert!(is_exporter_target("h2::proto::connection"));
+
+        assert!(!is_exporter_target("h2o_app"));
+        assert!(!is_exporter_target("reqwest_middleware::client"));
+        assert!(!is_exporter_target("tonic_health"));
+        assert!(!is_exporter_target("my_app::opentelemetry"));
+    }
+}
@@ -0,0 +1,83 @@
+use once_cell::sync::Lazy;
+use opentelemetry::global;
+use opentelemetry_otlp::MetricExporter;
+use opentelemetry_sdk::metrics::SdkMeterProvider;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::telemetry::{build_exporter, run_with_timeout};
+use crate::{Error, SubscriberConfig};
+
+const METRICS_PATH: &str = "/v1/metrics";
+
+/// Configure the OpenTelemetry meter provider, panicking on failure.
+/// See [`try_configure`] for the fallible version.
+pub fn configure<T>(config: &SubscriberConfig<T>) -> SdkMeterProvider {
+    try_configure(config).unwrap_or_else(|err| panic!("{err}"))
+}
+
+/// Configure the OpenTelemetry meter provider exporting metrics via OTLP and register it globally,
+/// so that meters can be obtained with [`opentelemetry::global::meter`].
+/// It shares the resource attributes of the tracer provider configured by [`crate::telemetry`].
+pub fn try_configure<T>(config: &SubscriberConfig<T>) -> Result<SdkMeterProvider, Error> {
+    let telemetry = config
+        .telemetry
+        .as_ref()
+        .ok_or(Error::MissingTelemetryConfig)?;
+
+    let otlp_exporter = build_exporter!(MetricExporter, config.env, telemetry, METRICS_PATH)?;
+
+    let meter_provider = SdkMeterProvider::builder()
+        .with_periodic_exporter(otlp_exporter)
+        .with_resource(crate::telemetry::resource(config, telemetry))
+        .build();
+
+    set_meter_provider(meter_provider.clone());
+
+    Ok(meter_provider)
+}
+
+static METER_PROVIDER: Lazy<Mutex<Option<SdkMeterProvider>>> = Lazy::new(Default::default);
+
+fn set_meter_provider(new_provider: SdkMeterProvider) {
+    global::set_meter_provider(new_provider.clone());
+
+    let mut meter_provider = METER_PROVIDER
+        .lock()
+        .expect("OpenTelemetry meter provider mutex poisoned");
+    meter_provider.replace(new_provider);
+}
+
+pub(crate) fn force_flush_meter_provider(timeout: Duration) -> Result<(), Error> {
+    let Some(meter_provider) = METER_PROVIDER
+        .lock()
+        .expect("OpenTelemetry meter provider mutex poisoned")
+        .clone()
+    else {
+        return Ok(());
+    };
+
+    run_with_timeout(timeout, move || meter_provider.force_flush()).map_err(|source| Error::Flush {
+        provider: "meter",
+        source,
+    })
+}
+
+/// Shut down the meter provider, doing nothing if it's missing or already shut down.
+pub(crate) fn shutdown_meter_provider(timeout: Duration) -> Result<(), Error> {
+    let Some(meter_provider) = METER_PROVIDER
+        .lock()
+        .expect("OpenTelemetry meter provider mutex poisoned")
+        .take()
+    else {
+        return Ok(());
+    };
+
+    run_with_timeout(timeout, move || {
+        meter_provider.shutdown_with_timeout(timeout)
+    })
+    .map_err(|source| Error::Shutdown {
+        provider: "meter",
+        source,
+    })
+}
@@ -1,11 +1,14 @@
+use std::time::{Duration, Instant};
+
 use tracing::{Event, Subscriber};
 use tracing_log::LogTracer;
 use tracing_subscriber::{
     layer::{Context, SubscriberExt},
-    EnvFilter,
+    registry::SpanRef,
+    Layer,
 };
 
-use crate::config::SubscriberConfig;
+use crate::{config::SubscriberConfig, Error, FilterHandle, NonBlockingWriter};
 
 pub struct Tracing;
 
@@ -16,43 +19,212 @@ impl Tracing {}
 /// - `default`: uses the [`tracing_subscriber::fmt::layer()`]
 /// - `json-logger`: activate the json logger
 /// - `traces`: activate spans export via `opentelemetry-otlp`
+/// - `logs`: activate events export via the `opentelemetry-otlp` logs pipeline
+/// - `metrics`: configure and register globally an `opentelemetry-otlp` meter provider
+///
+/// Panics if the subscriber cannot be configured, see [`try_configure_subscriber`].
 pub fn configure_subscriber<T: EventFormatter + Send + Sync + 'static>(
-    _config: SubscriberConfig<T>,
+    config: SubscriberConfig<T>,
 ) -> impl Subscriber + Send + Sync {
+    try_configure_subscriber(config).unwrap_or_else(|err| panic!("{err}"))
+}
+
+/// Configure a subscriber using [`SubscriberConfig`], returning an [`Error`] instead of panicking.
+/// See [`configure_subscriber`] for the available features.
+pub fn try_configure_subscriber<T: EventFormatter + Send + Sync + 'static>(
+    config: SubscriberConfig<T>,
+) -> Result<impl Subscriber + Send + Sync, Error> {
+    if let Some(sampling) = &config.sampling {
+        sampling.validate()?;
+    }
     let subscriber = tracing_subscriber::Registry::default();
-    let subscriber = subscriber.with(EnvFilter::from_default_env());
+    let filter = crate::filter::env_filter(&config.filter, config.env)?;
+    let (filter, filter_handle) = crate::filter::reloadable(filter);
+    let subscriber = subscriber.with(filter);
+    let output_filter = crate::filter::layer_filter(&config.filter.output_directives)?;
+    // Only the JSON logs and the exported spans and logs can be redacted, not the logs of
+    // `tracing_subscriber::fmt`
+    #[cfg(not(all(
+        feature = "redaction",
+        any(feature = "json-logger", feature = "traces")
+    )))]
+    if config.redaction.is_some() {
+        return Err(Error::RedactionNotSupported);
+    }
+    // Shared by the JSON logs and the exported spans and logs
+    #[cfg(all(
+        feature = "redaction",
+        any(feature = "json-logger", feature = "traces")
+    ))]
+    let redactor = config
+        .redaction
+        .as_ref()
+        .map(crate::redaction::Redactor::new)
+        .transpose()?
+        .map(std::sync::Arc::new);
 
     #[cfg(feature = "traces")]
     let subscriber = {
-        let tracer = crate::telemetry::configure(&_config);
+        #[cfg(feature = "redaction")]
+        let tracer = crate::telemetry::try_configure_with_redactor(&config, redactor.clone())?;
+        #[cfg(not(feature = "redaction"))]
+        let tracer = crate::telemetry::try_configure(&config)?;
         subscriber
-            .with(tracing_opentelemetry::layer().with_tracer(tracer))
-            .with(crate::layer::ErrorLayer)
+            .with(
+                tracing_opentelemetry::layer()
+                    .with_tracer(tracer)
+                    .with_filter(crate::filter::layer_filter(
+                        &config.filter.export_directives,
+                    )?),
+            )
+            .with(
+                crate::layer::ErrorLayer.with_filter(crate::filter::layer_filter(
+                    &config.filter.error_directives,
+                )?),
+            )
     };
 
+    #[cfg(feature = "metrics")]
+    crate::metrics::try_configure(&config)?;
+
+    #[cfg(feature = "logs")]
+    let subscriber = {
+        let logger_provider = crate::logs::try_configure(&config)?;
+        #[cfg(feature = "redaction")]
+        let logs_layer = crate::logs::redacting_layer(&logger_provider, redactor.clone());
+        #[cfg(not(feature = "redaction"))]
+        let logs_layer = crate::logs::layer(&logger_provider);
+        subscriber.with(logs_layer.with_filter(crate::filter::layer_filter(
+            &config.filter.export_directives,
+        )?))
+    };
+
+    let (writer, non_blocking_writer) = crate::writer::make_writer(
+        config.writer,
+        config.file_output.as_ref(),
+        config.non_blocking.as_ref(),
+    )?;
+
     #[cfg(not(feature = "json-logger"))]
-    let subscriber = subscriber.with(tracing_subscriber::fmt::layer());
+    let subscriber = {
+        let layer = tracing_subscriber::fmt::layer().with_writer(writer);
+        let layer = match config.ansi {
+            Some(ansi) => layer.with_ansi(ansi),
+            None => layer,
+        };
+        subscriber.with(layer.with_filter(output_filter))
+    };
     #[cfg(feature = "json-logger")]
     let subscriber = {
         use crate::json::formatter::PrimaFormattingLayer;
+        use crate::json::pretty::PrettyEventFormatter;
         use crate::json::storage::PrimaJsonStorage;
+        use std::io::IsTerminal;
+
+        let pretty = config
+            .pretty_output
+            .unwrap_or(config.env == crate::Environment::Dev);
+        let (json_layer, pretty_layer) = if pretty {
+            // Colors are only meant for a terminal, stdout unless another writer is set
+            let ansi = config
+                .ansi
+                .unwrap_or_else(|| std::io::stdout().is_terminal());
+            let pretty_layer = PrimaFormattingLayer::new(
+                config.service.clone(),
+                config.country.to_string(),
+                config.env.to_string(),
+                writer,
+                PrettyEventFormatter::default().with_ansi(ansi),
+            )
+            .with_span_close_level(config.span_close_level);
+            #[cfg(feature = "redaction")]
+            let pretty_layer = pretty_layer.with_redactor(redactor.clone());
+            (None, Some(pretty_layer))
+        } else {
+            let json_layer = PrimaFormattingLayer::new(
+                config.service.clone(),
+                config.country.to_string(),
+                config.env.to_string(),
+                writer,
+                config.json_formatter,
+            )
+            .with_span_close_level(config.span_close_level);
+            #[cfg(feature = "redaction")]
+            let json_layer = json_layer.with_redactor(redactor.clone());
+            (Some(json_layer), None)
+        };
+
+        let storage = if config.field_propagation {
+            PrimaJsonStorage::default().with_field_propagation()
+        } else {
+            PrimaJsonStorage::default()
+        };
+        #[cfg(feature = "redaction")]
+        let storage = storage.with_redactor(redactor);
+
         subscriber
-            .with(PrimaJsonStorage)
-            .with(PrimaFormattingLayer::new(
-                _config.service.clone(),
-                _config.country.to_string(),
-                _config.env.to_string(),
-                &std::io::stdout,
-                _config.json_formatter,
-            ))
+            .with(storage)
+            .with(Layer::and_then(json_layer, pretty_layer).with_filter(output_filter))
     };
 
-    subscriber
+    // Carried by the subscriber itself, to be found by `SubscriberHandles::for_subscriber`
+    let subscriber = subscriber.with(SubscriberHandles {
+        filter_handle,
+        non_blocking_writer,
+    });
+
+    Ok(subscriber)
+}
+
+/// Handles of a subscriber configured via [`try_configure_subscriber`], carried by the subscriber
+/// itself as a layer.
+///
+/// Unlike the [`Uninstall`] guard, they don't shut anything down when dropped, so they are the way
+/// to reach the handles of a subscriber not installed globally, eg. when used with
+/// [`tracing::subscriber::with_default`].
+#[derive(Clone)]
+pub struct SubscriberHandles {
+    filter_handle: FilterHandle,
+    non_blocking_writer: Option<NonBlockingWriter>,
+}
+
+impl SubscriberHandles {
+    /// The handles of `subscriber`, `None` if it has not been configured via
+    /// [`try_configure_subscriber`].
+    pub fn for_subscriber<S: Subscriber>(subscriber: &S) -> Option<Self> {
+        let subscriber: &dyn Subscriber = subscriber;
+        subscriber.downcast_ref::<SubscriberHandles>().cloned()
+    }
+
+    /// Handle changing the filter of the subscriber at runtime.
+    pub fn filter_handle(&self) -> FilterHandle {
+        self.filter_handle.clone()
+    }
+
+    /// The [`NonBlockingWriter`] of the subscriber, eg. to monitor its dropped lines, `None` if
+    /// the non-blocking writer is not enabled.
+    pub fn non_blocking_writer(&self) -> Option<NonBlockingWriter> {
+        self.non_blocking_writer.clone()
+    }
 }
+
+impl<S: Subscriber> Layer<S> for SubscriberHandles {}
 /// Initialize the subscriber and return the [`Uninstall`] guard
-pub fn init_subscriber(subscriber: impl Subscriber + Sync + Send) -> Uninstall {
-    LogTracer::init().expect("Failed to set logger");
-    tracing::subscriber::set_global_default(subscriber).expect("Setting default subscriber failed");
+///
+/// Panics if a logger or a global subscriber is already set, see [`try_init_subscriber`].
+pub fn init_subscriber(subscriber: impl Subscriber + Sync + Send + 'static) -> Uninstall {
+    try_init_subscriber(subscriber).unwrap_or_else(|err| panic!("{err}"))
+}
+/// Initialize the subscriber and return the [`Uninstall`] guard, returning an [`Error`]
+/// if a logger or a global subscriber is already set.
+pub fn try_init_subscriber(
+    subscriber: impl Subscriber + Sync + Send + 'static,
+) -> Result<Uninstall, Error> {
+    let handles = SubscriberHandles::for_subscriber(&subscriber);
+    LogTracer::init()?;
+    tracing::subscriber::set_global_default(subscriber)?;
+    // Only once installed, as dropping the guard shuts down the OpenTelemetry providers
+    let uninstall = Uninstall::from_handles(handles);
 
     #[cfg(feature = "traces")]
     {
@@ -60,7 +232,7 @@ pub fn init_subscriber(subscriber: impl Subscriber + Sync + Send) -> Uninstall {
         use opentelemetry_sdk::propagation::TraceContextPropagator;
         global::set_text_map_propagator(TraceContextPropagator::new());
     };
-    Uninstall
+    Ok(uninstall)
 }
 /// `EventFormatter` allows you to customise the format of [`tracing::Event`] if the `json-logger` feature is active
 pub trait EventFormatter {
@@ -72,22 +244,145 @@ pub trait EventFormatter {
     ) -> Result<Vec<u8>, std::io::Error>
     where
         S: Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>;
+
+    /// Format the line written when `span` closes, see
+    /// [`crate::SubscriberConfigBuilder::with_span_close_events`].
+    /// Nothing is written when the returned line is empty, as by default.
+    fn format_span_close<S>(
+        &self,
+        _span: &SpanRef<'_, S>,
+        _info: ContextInfo<'_>,
+    ) -> Result<Vec<u8>, std::io::Error>
+    where
+        S: Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>,
+    {
+        Ok(Vec::new())
+    }
 }
 /// Uninstall guard for doing works in shutdown
+///
+/// Returned by [`init_subscriber`], it can also be built with [`Uninstall::for_subscriber`] for a
+/// subscriber not installed globally. Dropping it shuts down the global OpenTelemetry providers,
+/// use [`SubscriberHandles`] to only reach the handles of a subscriber.
 #[must_use = "Uninstall guard shuts down the trace provider when dropped. By not using it/assigning it to a variable the tracing provider will be immediately removed."]
-pub struct Uninstall;
+pub struct Uninstall {
+    filter_handle: Option<FilterHandle>,
+    non_blocking_writer: Option<NonBlockingWriter>,
+}
+
+impl Uninstall {
+    /// The guard of `subscriber`, configured via [`try_configure_subscriber`] but not installed
+    /// globally with [`init_subscriber`], eg. when used with [`tracing::subscriber::with_default`].
+    /// Without the handles of a configured subscriber it only shuts down the OpenTelemetry
+    /// providers.
+    pub fn for_subscriber<S: Subscriber>(subscriber: &S) -> Self {
+        Self::from_handles(SubscriberHandles::for_subscriber(subscriber))
+    }
+
+    fn from_handles(handles: Option<SubscriberHandles>) -> Self {
+        match handles {
+            Some(handles) => Self {
+                filter_handle: Some(handles.filter_handle),
+                non_blocking_writer: handles.non_blocking_writer,
+            },
+            None => Self {
+                filter_handle: None,
+                non_blocking_writer: None,
+            },
+        }
+    }
+
+    /// Handle changing the filter of the subscriber at runtime, `None` if it has not been
+    /// configured via [`try_configure_subscriber`].
+    pub fn filter_handle(&self) -> Option<FilterHandle> {
+        self.filter_handle.clone()
+    }
+
+    /// The [`NonBlockingWriter`] of the subscriber, eg. to monitor its dropped lines, `None` if
+    /// the non-blocking writer is not enabled.
+    pub fn non_blocking_writer(&self) -> Option<NonBlockingWriter> {
+        self.non_blocking_writer.clone()
+    }
+
+    /// Export the spans, logs and metrics not exported yet, and write the pending log lines of the
+    /// non-blocking writer, waiting up to `timeout`.
+    /// Useful before the process gets frozen or killed, eg. at the end of an AWS Lambda invocation.
+    pub fn force_flush(&self, timeout: Duration) -> Result<(), Error> {
+        let deadline = Instant::now() + timeout;
+
+        #[cfg(feature = "metrics")]
+        crate::metrics::force_flush_meter_provider(remaining(deadline))?;
+        #[cfg(feature = "logs")]
+        crate::logs::force_flush_logger_provider(remaining(deadline))?;
+        #[cfg(feature = "traces")]
+        crate::telemetry::force_flush_tracer_provider(remaining(deadline))?;
+        self.flush_writer(deadline)
+    }
+
+    /// Shut down the OpenTelemetry providers exporting the pending data and write the pending log
+    /// lines of the non-blocking writer, waiting up to `timeout`.
+    /// Every provider is shut down even if one fails, the first error is returned.
+    /// Shutting down again, or dropping the guard afterwards, does nothing.
+    pub fn shutdown(self, timeout: Duration) -> Result<(), Error> {
+        self.shutdown_providers(timeout)
+    }
+
+    fn shutdown_providers(&self, timeout: Duration) -> Result<(), Error> {
+        let deadline = Instant::now() + timeout;
+        #[allow(unused_mut)]
+        let mut result = Ok(());
+
+        #[cfg(feature = "metrics")]
+        {
+            result = result.and(crate::metrics::shutdown_meter_provider(remaining(deadline)));
+        }
+        #[cfg(feature = "logs")]
+        {
+            result = result.and(crate::logs::shutdown_logger_provider(remaining(deadline)));
+        }
+        #[cfg(feature = "traces")]
+        {
+            result = result.and(crate::telemetry::shutdown_tracer_provider(remaining(
+                deadline,
+            )));
+        }
+
+        result.and(self.flush_writer(deadline))
+    }
+
+    fn flush_writer(&self, deadline: Instant) -> Result<(), Error> {
+        match &self.non_blocking_writer {
+            Some(writer) => writer.flush(remaining(deadline)),
+            None => Ok(()),
+        }
+    }
+}
 
 impl Drop for Uninstall {
     fn drop(&mut self) {
-        #[cfg(feature = "traces")]
-        crate::telemetry::shutdown_tracer_provider();
+        // eg. "Failed to shutdown the OpenTelemetry tracer provider: <reason>"
+        if let Err(err) = self.shutdown_providers(DEFAULT_SHUTDOWN_TIMEOUT) {
+            match std::error::Error::source(&err) {
+                Some(source) => eprintln!("{err}: {source}"),
+                None => eprintln!("{err}"),
+            }
+        }
     }
 }
+
+/// Same default used by the OpenTelemetry providers.
+const DEFAULT_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+fn remaining(deadline: Instant) -> Duration {
+    deadline.saturating_duration_since(Instant::now())
+}
 /// Information about the current app context like name or environment
 pub struct ContextInfo<'a> {
     pub(crate) app_name: &'a str,
     pub(crate) country: &'a str,
     pub(crate) environment: &'a str,
+    #[cfg(all(feature = "json-logger", feature = "redaction"))]
+    pub(crate) redactor: Option<&'a std::sync::Arc<crate::redaction::Redactor>>,
 }
 
 impl<'a> ContextInfo<'a> {
@@ -102,9 +397,20 @@ impl<'a> ContextInfo<'a> {
     pub fn environment(&self) -> &'a str {
         self.environment
     }
+
+    /// Create a visitor recording the fields of an event with the redaction configured via
+    /// [`crate::SubscriberConfigBuilder::with_redaction`], if any.
+    #[cfg(feature = "json-logger")]
+    pub fn json_visitor(&self) -> crate::json::storage::PrimaJsonVisitor<'static> {
+        let visitor = crate::json::storage::PrimaJsonVisitor::default();
+        #[cfg(feature = "redaction")]
+        let visitor = visitor.with_redactor(self.redactor.cloned());
+        visitor
+    }
 }
 
 #[cfg_attr(feature = "json-logger", allow(dead_code))]
+#[derive(Default)]
 pub struct NopEventFormatter;
 
 impl EventFormatter for NopEventFormatter {
@@ -120,3 +426,53 @@ impl EventFormatter for NopEventFormatter {
         Ok(vec![])
     }
 }
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn invalid_sampling_ratio_test() {
+        let config = crate::builder("test")
+            .with_country(crate::Country::Common)
+            .with_env(crate::Environment::Dev)
+            .with_sampling(crate::Sampling::TraceIdRatio(2.0))
+            .build();
+
+        assert!(matches!(
+            super::try_configure_subscriber(config).err(),
+            Some(crate::Error::InvalidSamplingRatio(ratio)) if ratio == 2.0
+        ));
+    }
+
+    #[cfg(not(feature = "redaction"))]
+    #[test]
+    fn redaction_without_feature_test() {
+        let config = crate::builder("test")
+            .with_country(crate::Country::Common)
+            .with_env(crate::Environment::Dev)
+            .with_telemetry("http://localhost:4318".to_string(), "test".to_string())
+            .with_redaction(crate::RedactionConfig::default().with_denied_field("password"))
+            .build();
+
+        assert!(matches!(
+            super::try_configure_subscriber(config).err(),
+            Some(crate::Error::RedactionNotSupported)
+        ));
+    }
+
+    #[cfg(all(
+        feature = "redaction",
+        feature = "traces",
+        not(feature = "json-logger")
+    ))]
+    #[test]
+    fn redaction_of_exported_spans_without_json_logger_test() {
+        let config = crate::builder("test")
+            .with_country(crate::Country::Common)
+            .with_env(crate::Environment::Dev)
+            .with_telemetry("http://localhost:4318".to_string(), "test".to_string())
+            .with_redaction(crate::RedactionConfig::default().with_denied_field("password"))
+            .build();
+
+        assert!(super::try_configure_subscriber(config).is_ok());
+    }
+}
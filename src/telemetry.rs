@@ -1,21 +1,31 @@
 use crate::resources::kube_env_resource;
 use once_cell::sync::Lazy;
 use opentelemetry::{global, trace::TracerProvider, InstrumentationScope, KeyValue};
-use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+use opentelemetry_otlp::SpanExporter;
+use opentelemetry_sdk::error::{OTelSdkError, OTelSdkResult};
 use opentelemetry_sdk::{trace as sdktrace, Resource};
 use opentelemetry_semantic_conventions::resource;
-use std::sync::Mutex;
+use std::path::Path;
+#[cfg(feature = "redaction")]
+use std::sync::Arc;
+use std::sync::{mpsc::RecvTimeoutError, Mutex};
+use std::time::Duration;
 
-use crate::SubscriberConfig;
+#[cfg(feature = "redaction")]
+use crate::redaction::{RedactingSpanProcessor, Redactor};
+use crate::{
+    BatchConfig, Environment, Error, FromEnvError, Sampling, SubscriberConfig, TelemetryConfig,
+    TelemetryProtocol, TlsConfig,
+};
 
-fn normalize_collector_url(collector_url: &str) -> String {
+fn normalize_collector_url(collector_url: &str, path: &str) -> String {
     // OTLP before version 0.15 didn't append a /v1/traces suffix, but started doing so there.
     // For backwards compatibility we strip it from configurations that do have it.
     let collector_url = collector_url
         // In case of a trailing slash strip it
         .strip_suffix('/')
         .unwrap_or(collector_url)
-        .strip_suffix("/v1/traces")
+        .strip_suffix(TRACES_PATH)
         .unwrap_or(collector_url);
 
     // Backport https://github.com/open-telemetry/opentelemetry-rust/pull/1553
@@ -24,23 +34,256 @@ fn normalize_collector_url(collector_url: &str) -> String {
     // And now starting from version 0.23 opentelemetry randomly stopped appending
     // the url suffix, so we need to do it ourselves.
     // This was not announced in the changelogs 🙃
-    collector_url.to_string() + "/v1/traces"
+    collector_url.to_string() + path
 }
 
-pub fn configure<T>(config: &SubscriberConfig<T>) -> sdktrace::Tracer {
-    let telemetry = config
-        .telemetry
-        .as_ref()
-        .expect("Telemetry config must be provided when the `traces` feature is enabled.");
+const TRACES_PATH: &str = "/v1/traces";
+
+/// Collector endpoint for the signal exported at `path` (eg. `/v1/traces`).
+/// The path is only appended for HTTP protocols, gRPC uses the collector URL as is.
+pub(crate) fn collector_endpoint(telemetry: &TelemetryConfig, path: &str) -> Result<String, Error> {
+    let endpoint = match telemetry.protocol {
+        TelemetryProtocol::HttpBinary | TelemetryProtocol::HttpJson => {
+            normalize_collector_url(&telemetry.collector_url, path)
+        }
+        #[cfg(feature = "grpc")]
+        TelemetryProtocol::Grpc => telemetry.collector_url.clone(),
+        #[cfg(not(feature = "grpc"))]
+        TelemetryProtocol::Grpc => return Err(Error::GrpcNotEnabled),
+    };
+
+    url::Url::parse(&endpoint).map_err(|source| Error::InvalidCollectorUrl {
+        url: telemetry.collector_url.clone(),
+        source,
+    })?;
+
+    Ok(endpoint)
+}
+
+/// Headers sent with every export request, rejecting names or values not allowed by HTTP.
+pub(crate) fn export_headers(telemetry: &TelemetryConfig) -> Result<http::HeaderMap, Error> {
+    telemetry
+        .headers
+        .iter()
+        .map(|(name, value)| {
+            let invalid = || Error::InvalidHeader { name: name.clone() };
+            Ok((
+                http::HeaderName::from_bytes(name.as_bytes()).map_err(|_| invalid())?,
+                http::HeaderValue::from_str(value).map_err(|_| invalid())?,
+            ))
+        })
+        .collect()
+}
+
+/// Default timeout of the export requests, as in the OpenTelemetry SDK.
+const DEFAULT_EXPORT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// PEM encoded files of a [`TlsConfig`].
+struct TlsFiles {
+    ca_certificate: Option<Vec<u8>>,
+    client_identity: Option<(Vec<u8>, Vec<u8>)>,
+}
+
+impl TlsFiles {
+    fn read(env: Environment, tls: &TlsConfig) -> Result<Self, Error> {
+        if tls.insecure && env != Environment::Dev {
+            return Err(Error::InvalidTlsConfig(
+                "insecure mode is only allowed in the dev environment",
+            ));
+        }
+
+        let read = |path: &Path| {
+            std::fs::read(path).map_err(|source| Error::ReadTlsFile {
+                path: path.to_path_buf(),
+                source,
+            })
+        };
+        let client_identity = match (&tls.client_certificate, &tls.client_key) {
+            (Some(certificate), Some(key)) => Some((read(certificate)?, read(key)?)),
+            (None, None) => None,
+            _ => {
+                return Err(Error::InvalidTlsConfig(
+                    "client certificate and key must be set together",
+                ))
+            }
+        };
+
+        Ok(Self {
+            ca_certificate: tls.ca_certificate.as_deref().map(read).transpose()?,
+            client_identity,
+        })
+    }
+}
+
+/// Read an environment variable, ignoring it when empty.
+fn env_var(name: &str) -> Option<String> {
+    std::env::var(name)
+        .ok()
+        .filter(|value| !value.trim().is_empty())
+}
+
+/// Timeout of the export requests sent by a custom HTTP client, which doesn't get the one
+/// configured by the exporter: `timeout` if any, otherwise `OTEL_EXPORTER_OTLP_TIMEOUT`.
+pub(crate) fn http_client_timeout(timeout: Option<Duration>) -> Result<Duration, Error> {
+    let timeout = match timeout {
+        Some(timeout) => Some(timeout),
+        None => crate::config::otlp_timeout(env_var)
+            .map_err(|err| Error::InvalidEnv(FromEnvError::new(vec![err])))?,
+    };
+
+    Ok(timeout.unwrap_or(DEFAULT_EXPORT_TIMEOUT))
+}
+
+/// HTTP client honouring the TLS settings of the [`TelemetryConfig`], if any,
+/// sending the export requests with `timeout`.
+pub(crate) fn http_client(
+    env: Environment,
+    telemetry: &TelemetryConfig,
+    timeout: Duration,
+) -> Result<Option<reqwest::blocking::Client>, Error> {
+    let Some(tls) = &telemetry.tls else {
+        return Ok(None);
+    };
+    let files = TlsFiles::read(env, tls)?;
+
+    let mut builder = reqwest::blocking::Client::builder()
+        .timeout(timeout)
+        .danger_accept_invalid_certs(tls.insecure);
+
+    if let Some(ca_certificate) = files.ca_certificate {
+        for certificate in
+            reqwest::Certificate::from_pem_bundle(&ca_certificate).map_err(Error::TlsSetup)?
+        {
+            builder = builder.add_root_certificate(certificate);
+        }
+    }
+    if let Some((certificate, key)) = files.client_identity {
+        let pem = [certificate, b"\n".to_vec(), key].concat();
+        builder = builder.identity(reqwest::Identity::from_pem(&pem).map_err(Error::TlsSetup)?);
+    }
+
+    // The blocking client panics when built within an async runtime
+    std::thread::spawn(move || builder.build())
+        .join()
+        .map_err(|_| Error::HttpClientPanic)?
+        .map(Some)
+        .map_err(Error::TlsSetup)
+}
+
+/// gRPC TLS settings of the [`TelemetryConfig`], if any.
+#[cfg(feature = "grpc")]
+pub(crate) fn grpc_tls_config(
+    env: Environment,
+    telemetry: &TelemetryConfig,
+) -> Result<Option<opentelemetry_otlp::tonic_types::transport::ClientTlsConfig>, Error> {
+    use opentelemetry_otlp::tonic_types::transport::{Certificate, ClientTlsConfig, Identity};
 
-    let collector_url = normalize_collector_url(&telemetry.collector_url);
+    let Some(tls) = &telemetry.tls else {
+        return Ok(None);
+    };
+    if tls.insecure {
+        return Err(Error::InvalidTlsConfig(
+            "insecure mode is not supported by the gRPC protocol",
+        ));
+    }
+    let files = TlsFiles::read(env, tls)?;
+
+    let mut config = ClientTlsConfig::new().with_webpki_roots();
+    if let Some(ca_certificate) = files.ca_certificate {
+        config = config.ca_certificate(Certificate::from_pem(ca_certificate));
+    }
+    if let Some((certificate, key)) = files.client_identity {
+        config = config.identity(Identity::from_pem(certificate, key));
+    }
+
+    Ok(Some(config))
+}
+
+/// Build an OTLP exporter (eg. [`SpanExporter`]) for the signal exported at `$path`,
+/// using the settings of the [`TelemetryConfig`] of the application running in `$env`.
+/// The optional `$timeout` is used when the [`TelemetryConfig`] doesn't set a request timeout.
+macro_rules! build_exporter {
+    ($exporter:ty, $env:expr, $telemetry:expr, $path:expr) => {
+        $crate::telemetry::build_exporter!($exporter, $env, $telemetry, $path, None)
+    };
+    ($exporter:ty, $env:expr, $telemetry:expr, $path:expr, $timeout:expr) => {{
+        #[cfg(feature = "grpc")]
+        use opentelemetry_otlp::WithTonicConfig as _;
+        use opentelemetry_otlp::{WithExportConfig as _, WithHttpConfig as _};
 
-    let otlp_exporter = SpanExporter::builder()
-        .with_http()
-        .with_endpoint(collector_url)
-        .build()
-        .expect("Failed to configure the OpenTelemetry OTLP span exporter");
+        let env: $crate::Environment = $env;
+        let telemetry: &$crate::TelemetryConfig = $telemetry;
+        let fallback_timeout: Option<std::time::Duration> = $timeout;
+        let timeout = telemetry.timeout.or(fallback_timeout);
+        let endpoint = $crate::telemetry::collector_endpoint(telemetry, $path)?;
+        let headers = $crate::telemetry::export_headers(telemetry)?;
 
+        match telemetry.protocol {
+            $crate::TelemetryProtocol::HttpBinary | $crate::TelemetryProtocol::HttpJson => {
+                let protocol = if telemetry.protocol == $crate::TelemetryProtocol::HttpJson {
+                    opentelemetry_otlp::Protocol::HttpJson
+                } else {
+                    opentelemetry_otlp::Protocol::HttpBinary
+                };
+                let headers = headers
+                    .iter()
+                    .filter_map(|(name, value)| {
+                        Some((name.to_string(), value.to_str().ok()?.to_string()))
+                    })
+                    .collect();
+                let mut builder = <$exporter>::builder()
+                    .with_http()
+                    .with_protocol(protocol)
+                    .with_endpoint(endpoint)
+                    .with_headers(headers);
+
+                if let Some(timeout) = timeout {
+                    builder = builder.with_timeout(timeout);
+                }
+                if telemetry.gzip_compression {
+                    builder = builder.with_compression(opentelemetry_otlp::Compression::Gzip);
+                }
+                let client_timeout = $crate::telemetry::http_client_timeout(timeout)?;
+                if let Some(client) =
+                    $crate::telemetry::http_client(env, telemetry, client_timeout)?
+                {
+                    builder = builder.with_http_client(client);
+                }
+                builder.build()
+            }
+            #[cfg(feature = "grpc")]
+            $crate::TelemetryProtocol::Grpc => {
+                let mut builder = <$exporter>::builder()
+                    .with_tonic()
+                    .with_endpoint(endpoint)
+                    .with_metadata(
+                        opentelemetry_otlp::tonic_types::metadata::MetadataMap::from_headers(
+                            headers,
+                        ),
+                    );
+
+                if let Some(timeout) = timeout {
+                    builder = builder.with_timeout(timeout);
+                }
+                if telemetry.gzip_compression {
+                    builder = builder.with_compression(opentelemetry_otlp::Compression::Gzip);
+                }
+                if let Some(tls_config) = $crate::telemetry::grpc_tls_config(env, telemetry)? {
+                    builder = builder.with_tls_config(tls_config);
+                }
+                builder.build()
+            }
+            #[cfg(not(feature = "grpc"))]
+            $crate::TelemetryProtocol::Grpc => {
+                unreachable!("gRPC is rejected by collector_endpoint without the grpc feature")
+            }
+        }
+    }};
+}
+pub(crate) use build_exporter;
+
+/// The [`Resource`] describing the application, shared by every OpenTelemetry signal.
+pub(crate) fn resource<T>(config: &SubscriberConfig<T>, telemetry: &TelemetryConfig) -> Resource {
     let resource_builder = Resource::builder()
         .with_service_name(telemetry.service_name.clone())
         .with_attribute(KeyValue::new("environment", config.env.to_string()))
@@ -56,20 +299,148 @@ pub fn configure<T>(config: &SubscriberConfig<T>) -> sdktrace::Tracer {
         resource_builder
     };
 
-    let resource = resource_builder.build();
+    resource_builder.build()
+}
+
+/// Configure the OpenTelemetry tracer provider, panicking on failure.
+/// See [`try_configure`] for the fallible version.
+pub fn configure<T>(config: &SubscriberConfig<T>) -> sdktrace::Tracer {
+    try_configure(config).unwrap_or_else(|err| panic!("{err}"))
+}
+
+/// Configure the OpenTelemetry tracer provider and register it globally.
+pub fn try_configure<T>(config: &SubscriberConfig<T>) -> Result<sdktrace::Tracer, Error> {
+    #[cfg(feature = "redaction")]
+    {
+        let redactor = config
+            .redaction
+            .as_ref()
+            .map(Redactor::new)
+            .transpose()?
+            .map(Arc::new);
+        try_configure_with_redactor(config, redactor)
+    }
+    #[cfg(not(feature = "redaction"))]
+    {
+        if config.redaction.is_some() {
+            return Err(Error::RedactionNotSupported);
+        }
+        configure_tracer(config, sdktrace::TracerProviderBuilder::with_span_processor)
+    }
+}
 
-    let tracer_provider = sdktrace::SdkTracerProvider::builder()
-        .with_batch_exporter(otlp_exporter)
-        .with_resource(resource)
+/// Like [`try_configure`], redacting the exported spans with the redactor already built from
+/// `config.redaction`.
+#[cfg(feature = "redaction")]
+pub(crate) fn try_configure_with_redactor<T>(
+    config: &SubscriberConfig<T>,
+    redactor: Option<Arc<Redactor>>,
+) -> Result<sdktrace::Tracer, Error> {
+    configure_tracer(config, |builder, batch_processor| match redactor {
+        Some(redactor) => {
+            builder.with_span_processor(RedactingSpanProcessor::new(batch_processor, redactor))
+        }
+        None => builder.with_span_processor(batch_processor),
+    })
+}
+
+/// Configure the tracer provider, registering the batch span processor with `add_processor`.
+fn configure_tracer<T>(
+    config: &SubscriberConfig<T>,
+    add_processor: impl FnOnce(
+        sdktrace::TracerProviderBuilder,
+        sdktrace::BatchSpanProcessor,
+    ) -> sdktrace::TracerProviderBuilder,
+) -> Result<sdktrace::Tracer, Error> {
+    let telemetry = config
+        .telemetry
+        .as_ref()
+        .ok_or(Error::MissingTelemetryConfig)?;
+
+    if let Some(sampling) = &config.sampling {
+        sampling.validate()?;
+    }
+    let batch = batch_settings(telemetry, env_var)?;
+
+    // The batch span processor exports from a dedicated thread waiting for the exporter,
+    // so the export timeout needs to be enforced on the exporter requests
+    let otlp_exporter = build_exporter!(
+        SpanExporter,
+        config.env,
+        telemetry,
+        TRACES_PATH,
+        batch.export_timeout
+    )?;
+    let resource = resource(config, telemetry);
+
+    let batch_processor = sdktrace::BatchSpanProcessor::builder(otlp_exporter)
+        .with_batch_config(batch_config(&batch))
         .build();
 
+    let tracer_provider_builder = add_processor(
+        sdktrace::SdkTracerProvider::builder().with_resource(resource),
+        batch_processor,
+    );
+
+    // When `OTEL_TRACES_SAMPLER` is set the SDK reads the sampler by itself
+    let sampling = match &config.sampling {
+        Some(sampling) => Some(sampling.clone()),
+        None if std::env::var_os("OTEL_TRACES_SAMPLER").is_some() => None,
+        None => Some(Sampling::for_environment(config.env)),
+    };
+
+    let tracer_provider = match sampling {
+        Some(sampling) => tracer_provider_builder.with_sampler(sampler(&sampling)),
+        None => tracer_provider_builder,
+    }
+    .build();
+
     set_tracer_provider(tracer_provider.clone());
 
     let scope = InstrumentationScope::builder("prima-tracing")
         .with_version(env!("CARGO_PKG_VERSION"))
         .build();
 
-    tracer_provider.tracer_with_scope(scope)
+    Ok(tracer_provider.tracer_with_scope(scope))
+}
+
+/// Batch settings of the [`TelemetryConfig`], with the unset ones read from the `OTEL_BSP_*`
+/// environment variables using `lookup`.
+fn batch_settings(
+    telemetry: &TelemetryConfig,
+    lookup: impl Fn(&str) -> Option<String>,
+) -> Result<BatchConfig, Error> {
+    let (from_env, errors) = BatchConfig::from_otel_env(lookup);
+    if !errors.is_empty() {
+        return Err(Error::InvalidEnv(FromEnvError::new(errors)));
+    }
+
+    Ok(telemetry.batch.clone().or(from_env))
+}
+
+fn batch_config(batch: &BatchConfig) -> sdktrace::BatchConfig {
+    let mut builder = sdktrace::BatchConfigBuilder::default();
+
+    if let Some(max_queue_size) = batch.max_queue_size {
+        builder = builder.with_max_queue_size(max_queue_size);
+    }
+    if let Some(max_export_batch_size) = batch.max_export_batch_size {
+        builder = builder.with_max_export_batch_size(max_export_batch_size);
+    }
+    if let Some(scheduled_delay) = batch.scheduled_delay {
+        builder = builder.with_scheduled_delay(scheduled_delay);
+    }
+
+    builder.build()
+}
+
+fn sampler(sampling: &Sampling) -> sdktrace::Sampler {
+    match sampling {
+        Sampling::AlwaysOn => sdktrace::Sampler::AlwaysOn,
+        Sampling::AlwaysOff => sdktrace::Sampler::AlwaysOff,
+        Sampling::TraceIdRatio(ratio) => sdktrace::Sampler::TraceIdRatioBased(*ratio),
+        Sampling::ParentBased(root) => sdktrace::Sampler::ParentBased(Box::new(sampler(root))),
+    }
 }
 
 // Consider to remove this wrapper when https://github.com/open-telemetry/opentelemetry-rust/issues/1961 is resolved
@@ -85,15 +456,59 @@ fn set_tracer_provider(new_provider: sdktrace::SdkTracerProvider) {
     tracer_provider.replace(new_provider);
 }
 
-pub(crate) fn shutdown_tracer_provider() {
-    let tracer_provider = TRACER_PROVIDER
+pub(crate) fn force_flush_tracer_provider(timeout: Duration) -> Result<(), Error> {
+    let Some(tracer_provider) = TRACER_PROVIDER
+        .lock()
+        .expect("OpenTelemetry tracer provider mutex poisoned")
+        .clone()
+    else {
+        return Ok(());
+    };
+
+    run_with_timeout(timeout, move || tracer_provider.force_flush()).map_err(|source| {
+        Error::Flush {
+            provider: "tracer",
+            source,
+        }
+    })
+}
+
+/// Shut down the tracer provider, doing nothing if it's missing or already shut down.
+pub(crate) fn shutdown_tracer_provider(timeout: Duration) -> Result<(), Error> {
+    let Some(tracer_provider) = TRACER_PROVIDER
         .lock()
         .expect("OpenTelemetry tracer provider mutex poisoned")
         .take()
-        .expect("OpenTelemetry tracer provider is missing, cannot shutdown");
+    else {
+        return Ok(());
+    };
 
-    if let Err(err) = tracer_provider.shutdown() {
-        eprintln!("Failed to shutdown the OpenTelemetry tracer provider: {err:?}");
+    run_with_timeout(timeout, move || {
+        tracer_provider.shutdown_with_timeout(timeout)
+    })
+    .map_err(|source| Error::Shutdown {
+        provider: "tracer",
+        source,
+    })
+}
+
+/// Run an `operation` of an OpenTelemetry provider on a separate thread, giving up after `timeout`
+/// as the providers don't bound the duration of every operation.
+pub(crate) fn run_with_timeout(
+    timeout: Duration,
+    operation: impl FnOnce() -> OTelSdkResult + Send + 'static,
+) -> OTelSdkResult {
+    let (sender, receiver) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let _ = sender.send(operation());
+    });
+
+    match receiver.recv_timeout(timeout) {
+        Ok(result) => result,
+        Err(RecvTimeoutError::Timeout) => Err(OTelSdkError::Timeout(timeout)),
+        Err(RecvTimeoutError::Disconnected) => Err(OTelSdkError::InternalFailure(
+            "the operation panicked".to_string(),
+        )),
     }
 }
 
@@ -106,15 +521,174 @@ mod test {
         let base = "http://localhost:8080";
         let expected = "http://localhost:8080/v1/traces";
 
-        assert_eq!(normalize_collector_url(base), expected);
+        assert_eq!(normalize_collector_url(base, TRACES_PATH), expected);
 
         let with_trailing_slash = format!("{base}/");
         assert_eq!(
-            normalize_collector_url(with_trailing_slash.as_str()),
+            normalize_collector_url(with_trailing_slash.as_str(), TRACES_PATH),
             expected
         );
 
         let complete = format!("{base}/v1/traces");
-        assert_eq!(normalize_collector_url(complete.as_str()), expected);
+        assert_eq!(
+            normalize_collector_url(complete.as_str(), TRACES_PATH),
+            expected
+        );
+    }
+
+    #[test]
+    fn http_client_timeout_test() {
+        // The configured timeout takes precedence over `OTEL_EXPORTER_OTLP_TIMEOUT`
+        assert_eq!(
+            http_client_timeout(Some(Duration::from_secs(3))).unwrap(),
+            Duration::from_secs(3)
+        );
+    }
+
+    #[test]
+    fn batch_settings_test() {
+        let telemetry =
+            TelemetryConfig::new("http://localhost:4318".to_string(), "test".to_string())
+                .with_batch_config(BatchConfig::default().with_max_queue_size(100));
+        let lookup = |name: &str| match name {
+            "OTEL_BSP_MAX_QUEUE_SIZE" => Some("10".to_string()),
+            "OTEL_BSP_MAX_EXPORT_BATCH_SIZE" => Some("50".to_string()),
+            _ => None,
+        };
+
+        // The configured values take precedence over the environment
+        let batch = batch_settings(&telemetry, lookup).unwrap();
+        assert_eq!(batch.max_queue_size, Some(100));
+        assert_eq!(batch.max_export_batch_size, Some(50));
+        assert_eq!(batch.scheduled_delay, None);
+
+        let lookup = |name: &str| (name == "OTEL_BSP_SCHEDULE_DELAY").then(|| "soon".to_string());
+        match batch_settings(&telemetry, lookup) {
+            Err(Error::InvalidEnv(err)) => assert_eq!(
+                err.errors(),
+                [crate::EnvVarError::Invalid {
+                    name: "OTEL_BSP_SCHEDULE_DELAY",
+                    value: "soon".to_string(),
+                    reason: "must be a non negative integer".to_string(),
+                }]
+            ),
+            _ => panic!("Expected an invalid environment error"),
+        }
+    }
+
+    #[test]
+    fn batch_config_test() {
+        let batch = BatchConfig::default()
+            .with_max_queue_size(100)
+            .with_max_export_batch_size(50)
+            .with_scheduled_delay(Duration::from_secs(2));
+
+        // The SDK doesn't expose the settings of its batch config but in the debug output
+        let config = format!("{:?}", batch_config(&batch));
+        assert!(config.contains("max_queue_size: 100"), "{config}");
+        assert!(config.contains("max_export_batch_size: 50"), "{config}");
+        assert!(config.contains("scheduled_delay: 2s"), "{config}");
+
+        let config = format!("{:?}", batch_config(&BatchConfig::default()));
+        assert!(config.contains("max_queue_size: 2048"), "{config}");
+        assert!(config.contains("max_export_batch_size: 512"), "{config}");
+        assert!(config.contains("scheduled_delay: 5s"), "{config}");
+    }
+
+    #[test]
+    fn try_configure_without_telemetry_test() {
+        let config = crate::builder("test")
+            .with_country(crate::Country::Common)
+            .with_env(crate::Environment::Dev)
+            .build();
+
+        assert!(matches!(
+            try_configure(&config),
+            Err(Error::MissingTelemetryConfig)
+        ));
+    }
+
+    #[test]
+    fn try_configure_invalid_collector_url_test() {
+        let config = crate::builder("test")
+            .with_country(crate::Country::Common)
+            .with_env(crate::Environment::Dev)
+            .with_telemetry("not a url".to_string(), "test".to_string())
+            .build();
+
+        assert!(matches!(
+            try_configure(&config),
+            Err(Error::InvalidCollectorUrl { .. })
+        ));
+    }
+
+    #[cfg(not(feature = "grpc"))]
+    #[test]
+    fn try_configure_grpc_without_feature_test() {
+        let config = crate::builder("test")
+            .with_country(crate::Country::Common)
+            .with_env(crate::Environment::Dev)
+            .with_telemetry_config(
+                TelemetryConfig::new("http://localhost:4317".to_string(), "test".to_string())
+                    .with_protocol(TelemetryProtocol::Grpc),
+            )
+            .build();
+
+        assert!(matches!(try_configure(&config), Err(Error::GrpcNotEnabled)));
+    }
+
+    #[test]
+    fn try_configure_invalid_header_test() {
+        let config = crate::builder("test")
+            .with_country(crate::Country::Common)
+            .with_env(crate::Environment::Dev)
+            .with_telemetry_config(
+                TelemetryConfig::new("http://localhost:4318".to_string(), "test".to_string())
+                    .with_header("Authorization".to_string(), "Bearer\nsecret".to_string()),
+            )
+            .build();
+
+        match try_configure(&config) {
+            Err(err @ Error::InvalidHeader { .. }) => {
+                assert!(!err.to_string().contains("secret"))
+            }
+            _ => panic!("Expected an invalid header error"),
+        }
+    }
+
+    #[test]
+    fn try_configure_insecure_tls_outside_dev_test() {
+        let config = crate::builder("test")
+            .with_country(crate::Country::Common)
+            .with_env(crate::Environment::Production)
+            .with_telemetry_config(
+                TelemetryConfig::new("https://localhost:4318".to_string(), "test".to_string())
+                    .with_tls_config(TlsConfig::default().with_insecure()),
+            )
+            .build();
+
+        assert!(matches!(
+            try_configure(&config),
+            Err(Error::InvalidTlsConfig(_))
+        ));
+    }
+
+    #[test]
+    fn try_configure_missing_tls_file_test() {
+        let config = crate::builder("test")
+            .with_country(crate::Country::Common)
+            .with_env(crate::Environment::Dev)
+            .with_telemetry_config(
+                TelemetryConfig::new("https://localhost:4318".to_string(), "test".to_string())
+                    .with_tls_config(
+                        TlsConfig::default().with_ca_certificate("/nonexistent/ca.pem"),
+                    ),
+            )
+            .build();
+
+        assert!(matches!(
+            try_configure(&config),
+            Err(Error::ReadTlsFile { .. })
+        ));
     }
 }
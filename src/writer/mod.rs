@@ -0,0 +1,33 @@
+use tracing_subscriber::fmt::writer::{BoxMakeWriter, MakeWriterExt};
+
+use crate::{Error, NonBlockingConfig, RollingFileConfig};
+
+pub use self::{non_blocking::NonBlockingWriter, rolling_file::RollingFileWriter};
+
+mod non_blocking;
+mod rolling_file;
+
+/// Build the writer of the logs from the configured one, writing to the rolling file instead of
+/// or alongside it, and wrapping the result in a [`NonBlockingWriter`] when enabled.
+/// The non-blocking writer is returned as well, to be flushed by the [`crate::Uninstall`] guard.
+pub(crate) fn make_writer(
+    make_writer: BoxMakeWriter,
+    file: Option<&RollingFileConfig>,
+    non_blocking: Option<&NonBlockingConfig>,
+) -> Result<(BoxMakeWriter, Option<NonBlockingWriter>), Error> {
+    let make_writer = match file {
+        Some(file) if file.stdout => {
+            BoxMakeWriter::new(make_writer.and(RollingFileWriter::new(file.clone())?))
+        }
+        Some(file) => BoxMakeWriter::new(RollingFileWriter::new(file.clone())?),
+        None => make_writer,
+    };
+
+    Ok(match non_blocking {
+        Some(config) => {
+            let non_blocking = NonBlockingWriter::new(make_writer, config);
+            (BoxMakeWriter::new(non_blocking.clone()), Some(non_blocking))
+        }
+        None => (make_writer, None),
+    })
+}
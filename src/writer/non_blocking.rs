@@ -0,0 +1,211 @@
+use std::io::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use tracing_subscriber::fmt::MakeWriter;
+
+use crate::{Error, NonBlockingConfig, OverflowPolicy};
+
+enum Message {
+    Line(Vec<u8>),
+    Flush(mpsc::Sender<()>),
+}
+
+/// Writer handing the log lines to a background thread, which writes them with the wrapped
+/// [`MakeWriter`], so that emitting an event doesn't wait for stdout.
+/// Enabled via [`crate::SubscriberConfigBuilder::with_non_blocking`], the writer of the
+/// configured subscriber is flushed by the [`crate::Uninstall`] guard.
+#[derive(Clone)]
+pub struct NonBlockingWriter {
+    sender: SyncSender<Message>,
+    overflow_policy: OverflowPolicy,
+    dropped_lines: Arc<AtomicU64>,
+}
+
+impl NonBlockingWriter {
+    /// Spawn the background thread writing the lines with `make_writer`.
+    pub fn new<W>(make_writer: W, config: &NonBlockingConfig) -> Self
+    where
+        W: for<'writer> MakeWriter<'writer> + Send + 'static,
+    {
+        let (sender, receiver) = mpsc::sync_channel(config.buffered_lines.max(1));
+        std::thread::Builder::new()
+            .name("prima-tracing-writer".to_string())
+            .spawn(move || write_lines(make_writer, receiver))
+            .expect("Failed to spawn the log writer thread");
+
+        Self {
+            sender,
+            overflow_policy: config.overflow_policy,
+            dropped_lines: Arc::default(),
+        }
+    }
+
+    /// Number of lines discarded since the writer has been created, because the buffer was full
+    /// with [`OverflowPolicy::Drop`] or the background thread stopped.
+    pub fn dropped_lines(&self) -> u64 {
+        self.dropped_lines.load(Ordering::Relaxed)
+    }
+
+    /// Wait up to `timeout` for the lines buffered so far to be written and flushed.
+    pub fn flush(&self, timeout: Duration) -> Result<(), Error> {
+        let deadline = Instant::now() + timeout;
+        let (ack, acked) = mpsc::channel();
+        let mut message = Message::Flush(ack);
+
+        // Unlike the lines, the flush request can't be dropped when the buffer is full
+        loop {
+            match self.sender.try_send(message) {
+                Ok(()) => break,
+                Err(TrySendError::Full(returned)) if Instant::now() < deadline => {
+                    message = returned;
+                    std::thread::sleep(Duration::from_millis(1));
+                }
+                Err(_) => return Err(Error::WriterFlush),
+            }
+        }
+
+        acked
+            .recv_timeout(deadline.saturating_duration_since(Instant::now()))
+            .map_err(|_| Error::WriterFlush)
+    }
+}
+
+impl Write for NonBlockingWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let line = Message::Line(buf.to_vec());
+        let sent = match self.overflow_policy {
+            OverflowPolicy::Drop => self.sender.try_send(line).is_ok(),
+            OverflowPolicy::Block => self.sender.send(line).is_ok(),
+        };
+        if !sent {
+            self.dropped_lines.fetch_add(1, Ordering::Relaxed);
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a> MakeWriter<'a> for NonBlockingWriter {
+    type Writer = NonBlockingWriter;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self.clone()
+    }
+}
+
+fn write_lines<W>(make_writer: W, receiver: Receiver<Message>)
+where
+    W: for<'writer> MakeWriter<'writer>,
+{
+    for message in receiver {
+        match message {
+            Message::Line(line) => {
+                let _ = make_writer.make_writer().write_all(&line);
+            }
+            Message::Flush(ack) => {
+                let _ = make_writer.make_writer().flush();
+                let _ = ack.send(());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::{Mutex, MutexGuard};
+
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl<'a> MakeWriter<'a> for Buffer {
+        type Writer = Self;
+
+        fn make_writer(&'a self) -> Self::Writer {
+            self.clone()
+        }
+    }
+
+    // Keeps the background thread waiting while a line is being written
+    fn hold(buffer: &Buffer) -> MutexGuard<'_, Vec<u8>> {
+        buffer.0.lock().unwrap()
+    }
+
+    #[test]
+    fn flush_writes_the_buffered_lines_test() {
+        let buffer = Buffer::default();
+        let writer = NonBlockingWriter::new(buffer.clone(), &NonBlockingConfig::default());
+
+        writer.make_writer().write_all(b"first\n").unwrap();
+        writer.make_writer().write_all(b"second\n").unwrap();
+        writer.flush(Duration::from_secs(5)).unwrap();
+
+        assert_eq!(hold(&buffer).as_slice(), b"first\nsecond\n");
+        assert_eq!(writer.dropped_lines(), 0);
+    }
+
+    #[test]
+    fn drop_policy_counts_dropped_lines_test() {
+        let buffer = Buffer::default();
+        let config = NonBlockingConfig::default().with_buffered_lines(1);
+        let writer = NonBlockingWriter::new(buffer.clone(), &config);
+
+        let held = hold(&buffer);
+        // The first line is taken by the background thread, the second fills the buffer
+        writer.make_writer().write_all(b"taken\n").unwrap();
+        while writer
+            .sender
+            .try_send(Message::Line(b"buffered\n".to_vec()))
+            .is_err()
+        {
+            std::thread::yield_now();
+        }
+        writer.make_writer().write_all(b"dropped\n").unwrap();
+        assert_eq!(writer.dropped_lines(), 1);
+        assert!(matches!(
+            writer.flush(Duration::from_millis(10)),
+            Err(Error::WriterFlush)
+        ));
+
+        drop(held);
+        writer.flush(Duration::from_secs(5)).unwrap();
+        assert_eq!(hold(&buffer).as_slice(), b"taken\nbuffered\n");
+    }
+
+    #[test]
+    fn block_policy_waits_for_room_test() {
+        let buffer = Buffer::default();
+        let config = NonBlockingConfig::default()
+            .with_buffered_lines(1)
+            .with_overflow_policy(OverflowPolicy::Block);
+        let writer = NonBlockingWriter::new(buffer.clone(), &config);
+
+        for line in 0..100 {
+            writer
+                .make_writer()
+                .write_all(format!("{line}\n").as_bytes())
+                .unwrap();
+        }
+        writer.flush(Duration::from_secs(5)).unwrap();
+
+        assert_eq!(writer.dropped_lines(), 0);
+        assert_eq!(hold(&buffer).split(|b| *b == b'\n').count(), 101);
+    }
+}
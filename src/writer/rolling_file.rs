@@ -0,0 +1,227 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use chrono::{DateTime, DurationRound, TimeDelta, Utc};
+use tracing_subscriber::fmt::MakeWriter;
+
+use crate::{Error, RollingFileConfig, Rotation};
+
+/// Writer appending the logs to a file rotated by size or time, see [`RollingFileConfig`].
+/// Selected via [`crate::SubscriberConfigBuilder::with_file_output`].
+#[derive(Clone)]
+pub struct RollingFileWriter(Arc<Mutex<RollingFile>>);
+
+impl RollingFileWriter {
+    /// Open, or create, the log file at `config.path`.
+    pub fn new(config: RollingFileConfig) -> Result<Self, Error> {
+        let file = RollingFile::open(config, Utc::now())?;
+        Ok(Self(Arc::new(Mutex::new(file))))
+    }
+}
+
+impl<'a> MakeWriter<'a> for RollingFileWriter {
+    type Writer = RollingFileGuard<'a>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        RollingFileGuard(self.0.lock().unwrap_or_else(PoisonError::into_inner))
+    }
+}
+
+/// Exclusive access to the log file, so that lines written concurrently are not interleaved.
+pub struct RollingFileGuard<'a>(MutexGuard<'a, RollingFile>);
+
+impl Write for RollingFileGuard<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.write(buf, Utc::now())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.file.flush()
+    }
+}
+
+struct RollingFile {
+    config: RollingFileConfig,
+    file: File,
+    size: u64,
+    // Beginning of the next period with the time based rotations
+    next_rotation: Option<DateTime<Utc>>,
+}
+
+impl RollingFile {
+    fn open(config: RollingFileConfig, now: DateTime<Utc>) -> Result<Self, Error> {
+        let open_error = |source| Error::OpenLogFile {
+            path: config.path.clone(),
+            source,
+        };
+        if let Some(directory) = config
+            .path
+            .parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+        {
+            fs::create_dir_all(directory).map_err(open_error)?;
+        }
+        let file = append(&config.path).map_err(open_error)?;
+        let metadata = file.metadata().map_err(open_error)?;
+
+        // A file left by a previous run is rotated at the end of the period it was written in
+        let last_write = metadata
+            .modified()
+            .map(DateTime::<Utc>::from)
+            .map_or(now, |modified| modified.min(now));
+
+        Ok(Self {
+            next_rotation: next_rotation(config.rotation, last_write),
+            size: metadata.len(),
+            file,
+            config,
+        })
+    }
+
+    fn write(&mut self, buf: &[u8], now: DateTime<Utc>) -> io::Result<usize> {
+        if self.should_rotate(buf.len() as u64, now) {
+            // When the rotation fails the lines keep being appended to the current file,
+            // and the rotation is attempted again with the next line
+            let _ = self.rotate(now);
+        }
+        // Lines are written at once, so that they are never split between two files
+        self.file.write_all(buf)?;
+        self.size += buf.len() as u64;
+        Ok(buf.len())
+    }
+
+    fn should_rotate(&self, len: u64, now: DateTime<Utc>) -> bool {
+        match (self.config.rotation, self.next_rotation) {
+            (Rotation::Size(max_size), _) => self.size > 0 && self.size + len > max_size,
+            (_, Some(next_rotation)) => now >= next_rotation,
+            (_, None) => false,
+        }
+    }
+
+    fn rotate(&mut self, now: DateTime<Utc>) -> io::Result<()> {
+        let path = &self.config.path;
+        let max_archives = self.config.max_archives;
+
+        if max_archives == 0 {
+            self.file.set_len(0)?;
+        } else {
+            match fs::remove_file(archive(path, max_archives)) {
+                Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+                _ => {}
+            }
+            for index in (1..max_archives).rev() {
+                let from = archive(path, index);
+                if from.exists() {
+                    fs::rename(from, archive(path, index + 1))?;
+                }
+            }
+            fs::rename(path, archive(path, 1))?;
+            self.file = append(path)?;
+        }
+
+        self.size = 0;
+        self.next_rotation = next_rotation(self.config.rotation, now);
+        Ok(())
+    }
+}
+
+fn append(path: &Path) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+/// Path of the `index`-th most recent rotated file, eg. `app.log.1`.
+fn archive(path: &Path, index: usize) -> PathBuf {
+    let mut archive = path.as_os_str().to_owned();
+    archive.push(format!(".{index}"));
+    archive.into()
+}
+
+fn next_rotation(rotation: Rotation, from: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let period = match rotation {
+        Rotation::Size(_) => return None,
+        Rotation::Hourly => TimeDelta::hours(1),
+        Rotation::Daily => TimeDelta::days(1),
+    };
+    from.duration_trunc(period).ok().map(|start| start + period)
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    /// Temporary directory removed along with the log files when dropped.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new() -> Self {
+            Self(std::env::temp_dir().join(format!("prima-tracing-{}", uuid::Uuid::new_v4())))
+        }
+
+        fn log_path(&self) -> PathBuf {
+            self.0.join("app.log")
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn read(path: &Path) -> String {
+        fs::read_to_string(path).unwrap_or_default()
+    }
+
+    #[test]
+    fn size_rotation_test() {
+        let dir = TempDir::new();
+        let path = dir.log_path();
+        let config = RollingFileConfig::new(&path, Rotation::Size(10)).with_max_archives(2);
+        let now = Utc::now();
+        let mut file = RollingFile::open(config, now).unwrap();
+
+        for line in ["first\n", "second\n", "third\n", "fourth\n"] {
+            file.write(line.as_bytes(), now).unwrap();
+        }
+
+        assert_eq!(read(&path), "fourth\n");
+        assert_eq!(read(&archive(&path, 1)), "third\n");
+        assert_eq!(read(&archive(&path, 2)), "second\n");
+        assert!(!archive(&path, 3).exists());
+    }
+
+    #[test]
+    fn hourly_rotation_test() {
+        let dir = TempDir::new();
+        let path = dir.log_path();
+        let config = RollingFileConfig::new(&path, Rotation::Hourly);
+        let at = |hour, minute| Utc.with_ymd_and_hms(2024, 1, 1, hour, minute, 0).unwrap();
+        let mut file = RollingFile::open(config, at(10, 30)).unwrap();
+
+        file.write(b"first\n", at(10, 30)).unwrap();
+        file.write(b"second\n", at(10, 59)).unwrap();
+        file.write(b"third\n", at(11, 0)).unwrap();
+
+        assert_eq!(read(&path), "third\n");
+        assert_eq!(read(&archive(&path, 1)), "first\nsecond\n");
+    }
+
+    #[test]
+    fn daily_rotation_without_archives_test() {
+        let dir = TempDir::new();
+        let path = dir.log_path();
+        let config = RollingFileConfig::new(&path, Rotation::Daily).with_max_archives(0);
+        let on = |day| Utc.with_ymd_and_hms(2024, 1, day, 12, 0, 0).unwrap();
+        let mut file = RollingFile::open(config, on(1)).unwrap();
+
+        file.write(b"first\n", on(1)).unwrap();
+        file.write(b"second\n", on(2)).unwrap();
+
+        assert_eq!(read(&path), "second\n");
+        assert!(!archive(&path, 1).exists());
+    }
+}
@@ -0,0 +1,198 @@
+//! OTLP collector stand-ins shared by the integration tests
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::sync::{mpsc, Arc};
+use std::time::{Duration, Instant};
+
+use rcgen::{BasicConstraints, Certificate, CertificateParams, CertifiedKey, IsCa, KeyPair};
+use rustls::{
+    pki_types::PrivatePkcs8KeyDer, server::WebPkiClientVerifier, RootCertStore, ServerConfig,
+    ServerConnection, StreamOwned,
+};
+
+/// A request received by the HTTP collector stand-in
+pub struct HttpRequest {
+    pub path: String,
+    pub content_type: String,
+    /// Every header, with lowercase names
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl HttpRequest {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Minimal OTLP/HTTP receiver answering `200 OK` to every request
+pub fn http_collector() -> (String, mpsc::Receiver<HttpRequest>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let (sender, receiver) = mpsc::channel();
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let _ = serve(stream.unwrap(), &sender);
+        }
+    });
+
+    (url, receiver)
+}
+
+/// Certificates signed by a throwaway CA for the TLS stand-in collector and its clients
+pub struct TestPki {
+    pub ca: Certificate,
+    pub server: CertifiedKey,
+    pub client: CertifiedKey,
+}
+
+impl TestPki {
+    pub fn generate() -> Self {
+        let ca_key = KeyPair::generate().unwrap();
+        let mut ca_params = CertificateParams::new(Vec::<String>::new()).unwrap();
+        ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        let ca = ca_params.self_signed(&ca_key).unwrap();
+
+        let sign = |name: &str| {
+            let key_pair = KeyPair::generate().unwrap();
+            let cert = CertificateParams::new(vec![name.to_string()])
+                .unwrap()
+                .signed_by(&key_pair, &ca, &ca_key)
+                .unwrap();
+            CertifiedKey { cert, key_pair }
+        };
+
+        Self {
+            server: sign("127.0.0.1"),
+            client: sign("client"),
+            ca,
+        }
+    }
+}
+
+/// Write `pem` to a temporary file, returning its path
+pub fn write_pem(pem: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("prima-tracing-{}.pem", uuid::Uuid::new_v4()));
+    std::fs::write(&path, pem).unwrap();
+    path
+}
+
+/// OTLP/HTTP receiver behind TLS, using the server certificate of `pki`.
+/// With `client_auth` only clients presenting a certificate signed by the CA are accepted.
+pub fn https_collector(pki: &TestPki, client_auth: bool) -> (String, mpsc::Receiver<HttpRequest>) {
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let builder = ServerConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .unwrap();
+    let builder = if client_auth {
+        let mut roots = RootCertStore::empty();
+        roots.add(pki.ca.der().clone()).unwrap();
+        let verifier = WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider)
+            .build()
+            .unwrap();
+        builder.with_client_cert_verifier(verifier)
+    } else {
+        builder.with_no_client_auth()
+    };
+    let config = Arc::new(
+        builder
+            .with_single_cert(
+                vec![pki.server.cert.der().clone()],
+                PrivatePkcs8KeyDer::from(pki.server.key_pair.serialize_der()).into(),
+            )
+            .unwrap(),
+    );
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("https://{}", listener.local_addr().unwrap());
+    let (sender, receiver) = mpsc::channel();
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let connection = ServerConnection::new(config.clone()).unwrap();
+            // Connections failing the handshake are dropped
+            let _ = serve(StreamOwned::new(connection, stream.unwrap()), &sender);
+        }
+    });
+
+    (url, receiver)
+}
+
+/// Answer a single request received on `stream`, forwarding it to `sender`
+fn serve(stream: impl Read + Write, sender: &mpsc::Sender<HttpRequest>) -> io::Result<()> {
+    let mut reader = BufReader::new(stream);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let path = request_line
+        .split_whitespace()
+        .nth(1)
+        .unwrap_or_default()
+        .to_string();
+
+    let mut content_type = String::new();
+    let mut content_length = 0;
+    let mut headers = Vec::new();
+    loop {
+        let mut header = String::new();
+        reader.read_line(&mut header)?;
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            let (name, value) = (name.to_lowercase(), value.trim().to_string());
+            match name.as_str() {
+                "content-type" => content_type = value.clone(),
+                "content-length" => content_length = value.parse().unwrap(),
+                _ => {}
+            }
+            headers.push((name, value));
+        }
+    }
+
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+
+    let response_body = if content_type == "application/json" {
+        "{}"
+    } else {
+        ""
+    };
+    let stream = reader.get_mut();
+    write!(
+        stream,
+        "HTTP/1.1 200 OK\r\ncontent-type: {content_type}\r\ncontent-length: {}\r\n\r\n{response_body}",
+        response_body.len()
+    )?;
+    stream.flush()?;
+
+    let _ = sender.send(HttpRequest {
+        path,
+        content_type,
+        headers,
+        body,
+    });
+    Ok(())
+}
+
+/// Wait for the first request sent to `path`, ignoring the other signals
+pub fn receive(requests: &mpsc::Receiver<HttpRequest>, path: &str) -> HttpRequest {
+    let deadline = Instant::now() + Duration::from_secs(10);
+
+    loop {
+        let timeout = deadline.saturating_duration_since(Instant::now());
+        let request = requests
+            .recv_timeout(timeout)
+            .unwrap_or_else(|_| panic!("No request received on {path}"));
+
+        if request.path == path {
+            return request;
+        }
+    }
+}
@@ -29,4 +29,58 @@ mod tests {
 2: TestError"#
         );
     }
+
+    #[cfg(feature = "json-logger")]
+    #[test]
+    fn it_records_a_single_error_kind() {
+        use std::io::Write;
+        use std::sync::{Arc, Mutex};
+
+        use prima_tracing::json::formatter::{DefaultEventFormatter, PrimaFormattingLayer};
+        use tracing_subscriber::{fmt::MakeWriter, layer::SubscriberExt};
+
+        #[derive(Clone, Default)]
+        struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+        impl Write for Buffer {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.lock().unwrap().write(buf)
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        impl<'a> MakeWriter<'a> for Buffer {
+            type Writer = Self;
+
+            fn make_writer(&'a self) -> Self::Writer {
+                self.clone()
+            }
+        }
+
+        let buffer = Buffer::default();
+        let subscriber = tracing_subscriber::registry()
+            .with(prima_tracing::json::storage::layer())
+            .with(PrimaFormattingLayer::new(
+                "error-report".to_string(),
+                "common".to_string(),
+                "production".to_string(),
+                buffer.clone(),
+                DefaultEventFormatter::default(),
+            ));
+
+        tracing::subscriber::with_default(subscriber, || {
+            let error = std::io::Error::new(std::io::ErrorKind::NotFound, "missing file");
+            prima_tracing::report_error!(error, "Failed to read the file");
+        });
+
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let line: serde_json::Value = serde_json::from_str(output.trim()).unwrap();
+        // The nested kind of IO errors doesn't clash with the one recorded by the macro
+        check!(line["error.kind"] == "std::io::error::Error");
+        check!(line["error"]["io_kind"] == "NotFound");
+        check!(line["error"].get("kind").is_none());
+    }
 }
@@ -0,0 +1,64 @@
+use prima_tracing::{
+    builder, configure_subscriber, init_subscriber, Country, Environment, SubscriberHandles,
+};
+use tracing::Level;
+
+#[test]
+fn filter_handle_changes_the_filter_at_runtime() {
+    let subscriber = configure_subscriber(
+        builder("filter-reload")
+            .with_country(Country::Common)
+            .with_env(Environment::Dev)
+            .with_telemetry(
+                "http://localhost:55681".to_string(),
+                "filter-reload".to_string(),
+            )
+            .build(),
+    );
+    let uninstall = init_subscriber(subscriber);
+    let handle = uninstall.filter_handle().expect("Missing filter handle");
+
+    handle.set_directives("warn,filter_reload=debug").unwrap();
+    assert!(tracing::enabled!(Level::DEBUG));
+    assert!(!tracing::enabled!(target: "other_crate", Level::INFO));
+
+    assert!(handle.set_directives("filter_reload=verbose").is_err());
+    assert!(tracing::enabled!(Level::DEBUG));
+
+    handle.set_directives("error").unwrap();
+    assert!(!tracing::enabled!(Level::DEBUG));
+}
+
+#[test]
+fn filter_handle_changes_only_its_subscriber() {
+    let configure = || {
+        configure_subscriber(
+            builder("filter-reload")
+                .with_country(Country::Common)
+                .with_env(Environment::Production)
+                .with_telemetry(
+                    "http://localhost:55681".to_string(),
+                    "filter-reload".to_string(),
+                )
+                .build(),
+        )
+    };
+    let subscriber = configure();
+    let other_subscriber = configure();
+    let handle = SubscriberHandles::for_subscriber(&subscriber)
+        .expect("Missing subscriber handles")
+        .filter_handle();
+    let other_handle = SubscriberHandles::for_subscriber(&other_subscriber)
+        .expect("Missing subscriber handles")
+        .filter_handle();
+
+    // Configuring another subscriber doesn't make the handle change it
+    handle.set_directives("debug").unwrap();
+    assert_eq!(other_handle.directives().as_deref(), Some("info"));
+    tracing::subscriber::with_default(subscriber, || {
+        assert!(tracing::enabled!(Level::DEBUG));
+    });
+    tracing::subscriber::with_default(other_subscriber, || {
+        assert!(!tracing::enabled!(Level::DEBUG));
+    });
+}
@@ -0,0 +1,26 @@
+use prima_tracing::{
+    builder, try_configure_subscriber, try_init_subscriber, Country, Environment, Error,
+};
+
+fn subscriber() -> impl tracing::Subscriber + Send + Sync {
+    try_configure_subscriber(
+        builder("init-test")
+            .with_country(Country::Common)
+            .with_env(Environment::Dev)
+            .with_telemetry(
+                "http://localhost:55681".to_string(),
+                "init-test".to_string(),
+            )
+            .build(),
+    )
+    .expect("Failed to configure subscriber")
+}
+
+#[test]
+fn try_init_subscriber_fails_when_already_initialized() {
+    let _guard = try_init_subscriber(subscriber()).expect("Failed to init subscriber");
+
+    let error = try_init_subscriber(subscriber()).err().unwrap();
+
+    assert!(matches!(error, Error::LoggerAlreadySet(_)));
+}
@@ -0,0 +1,329 @@
+use std::sync::Mutex;
+use std::time::Duration;
+
+mod collector;
+
+use collector::{http_collector, https_collector, receive, write_pem, TestPki};
+
+use prima_tracing::{
+    builder, try_configure_subscriber, BatchConfig, Country, Environment, TelemetryConfig,
+    TelemetryProtocol, TlsConfig, Uninstall,
+};
+
+// The providers are globals, so tests exporting spans must not overlap
+static EXPORT_LOCK: Mutex<()> = Mutex::new(());
+
+const SPAN_NAME: &str = "otlp-protocol-test-span";
+
+fn export_span(telemetry: TelemetryConfig) {
+    let subscriber = try_configure_subscriber(
+        builder("otlp-protocols")
+            .with_country(Country::Common)
+            .with_env(Environment::Dev)
+            .with_telemetry_config(telemetry)
+            .build(),
+    )
+    .expect("Failed to configure subscriber");
+    let uninstall = Uninstall::for_subscriber(&subscriber);
+
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::error_span!(SPAN_NAME).in_scope(|| tracing::error!("hello"));
+    });
+
+    // Shuts down the tracer provider, flushing the pending spans
+    drop(uninstall);
+}
+
+#[test]
+fn exports_spans_with_http_protobuf() {
+    let _lock = EXPORT_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    let (url, requests) = http_collector();
+
+    export_span(
+        TelemetryConfig::new(format!("{url}/"), "otlp-protocols".to_string())
+            .with_protocol(TelemetryProtocol::HttpBinary),
+    );
+
+    let request = receive(&requests, "/v1/traces");
+    assert_eq!(request.content_type, "application/x-protobuf");
+    assert!(request
+        .body
+        .windows(SPAN_NAME.len())
+        .any(|window| window == SPAN_NAME.as_bytes()));
+}
+
+#[test]
+fn exports_spans_with_http_json() {
+    let _lock = EXPORT_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    let (url, requests) = http_collector();
+
+    export_span(
+        TelemetryConfig::new(url, "otlp-protocols".to_string())
+            .with_protocol(TelemetryProtocol::HttpJson),
+    );
+
+    let request = receive(&requests, "/v1/traces");
+    assert_eq!(request.content_type, "application/json");
+
+    let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+    assert_eq!(
+        body["resourceSpans"][0]["scopeSpans"][0]["spans"][0]["name"],
+        SPAN_NAME
+    );
+}
+
+#[test]
+fn exports_spans_with_headers_and_gzip_compression() {
+    let _lock = EXPORT_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    let (url, requests) = http_collector();
+
+    export_span(
+        TelemetryConfig::new(url, "otlp-protocols".to_string())
+            .with_header("Authorization".to_string(), "Bearer token".to_string())
+            .with_header("x-tenant".to_string(), "prima".to_string())
+            .with_timeout(Duration::from_secs(5))
+            .with_gzip_compression(),
+    );
+
+    let request = receive(&requests, "/v1/traces");
+    assert_eq!(request.header("authorization"), Some("Bearer token"));
+    assert_eq!(request.header("x-tenant"), Some("prima"));
+    assert_eq!(request.header("content-encoding"), Some("gzip"));
+    // gzip magic number
+    assert_eq!(request.body[..2], [0x1f, 0x8b]);
+}
+
+#[test]
+fn exports_spans_with_tls_and_custom_ca() {
+    let _lock = EXPORT_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    let pki = TestPki::generate();
+    let (url, requests) = https_collector(&pki, false);
+
+    export_span(
+        TelemetryConfig::new(url, "otlp-protocols".to_string())
+            .with_tls_config(TlsConfig::default().with_ca_certificate(write_pem(&pki.ca.pem()))),
+    );
+
+    let request = receive(&requests, "/v1/traces");
+    assert_eq!(request.content_type, "application/x-protobuf");
+}
+
+#[test]
+fn exports_spans_with_mutual_tls() {
+    let _lock = EXPORT_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    let pki = TestPki::generate();
+    let (url, requests) = https_collector(&pki, true);
+
+    export_span(
+        TelemetryConfig::new(url, "otlp-protocols".to_string()).with_tls_config(
+            TlsConfig::default()
+                .with_ca_certificate(write_pem(&pki.ca.pem()))
+                .with_client_identity(
+                    write_pem(&pki.client.cert.pem()),
+                    write_pem(&pki.client.key_pair.serialize_pem()),
+                ),
+        ),
+    );
+
+    let request = receive(&requests, "/v1/traces");
+    assert_eq!(request.content_type, "application/x-protobuf");
+}
+
+#[test]
+fn exports_spans_with_insecure_tls_in_dev() {
+    let _lock = EXPORT_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    let pki = TestPki::generate();
+    let (url, requests) = https_collector(&pki, false);
+
+    export_span(
+        TelemetryConfig::new(url, "otlp-protocols".to_string())
+            .with_tls_config(TlsConfig::default().with_insecure()),
+    );
+
+    let request = receive(&requests, "/v1/traces");
+    assert_eq!(request.content_type, "application/x-protobuf");
+}
+
+#[test]
+fn export_directives_restrict_exported_spans() {
+    let _lock = EXPORT_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    let (url, requests) = http_collector();
+
+    let subscriber = try_configure_subscriber(
+        builder("otlp-protocols")
+            .with_country(Country::Common)
+            .with_env(Environment::Dev)
+            .with_telemetry(url, "otlp-protocols".to_string())
+            .with_default_directives("debug".to_string())
+            .with_export_directives("warn".to_string())
+            .build(),
+    )
+    .expect("Failed to configure subscriber");
+    let uninstall = Uninstall::for_subscriber(&subscriber);
+
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::debug_span!("not-exported-span").in_scope(|| {
+            tracing::error_span!(SPAN_NAME).in_scope(|| tracing::error!("hello"));
+        });
+    });
+    drop(uninstall);
+
+    let request = receive(&requests, "/v1/traces");
+    let contains = |expected: &str| {
+        request
+            .body
+            .windows(expected.len())
+            .any(|window| window == expected.as_bytes())
+    };
+    assert!(contains(SPAN_NAME));
+    assert!(!contains("not-exported-span"));
+}
+
+#[test]
+fn force_flush_exports_pending_spans() {
+    let _lock = EXPORT_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    let (url, requests) = http_collector();
+
+    let subscriber = try_configure_subscriber(
+        builder("otlp-protocols")
+            .with_country(Country::Common)
+            .with_env(Environment::Dev)
+            .with_telemetry_config(
+                TelemetryConfig::new(url, "otlp-protocols".to_string()).with_batch_config(
+                    BatchConfig::default().with_scheduled_delay(Duration::from_secs(3600)),
+                ),
+            )
+            .build(),
+    )
+    .expect("Failed to configure subscriber");
+    let uninstall = Uninstall::for_subscriber(&subscriber);
+    let other_uninstall = Uninstall::for_subscriber(&subscriber);
+
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::error_span!(SPAN_NAME).in_scope(|| tracing::error!("hello"));
+    });
+
+    uninstall.force_flush(Duration::from_secs(5)).unwrap();
+    let request = receive(&requests, "/v1/traces");
+    assert!(request
+        .body
+        .windows(SPAN_NAME.len())
+        .any(|window| window == SPAN_NAME.as_bytes()));
+
+    uninstall.shutdown(Duration::from_secs(5)).unwrap();
+    // Shutting down again is a no-op
+    other_uninstall.force_flush(Duration::from_secs(5)).unwrap();
+    other_uninstall.shutdown(Duration::from_secs(5)).unwrap();
+}
+
+#[cfg(feature = "grpc")]
+mod grpc {
+    use opentelemetry_proto::tonic::collector::trace::v1::{
+        trace_service_server::{TraceService, TraceServiceServer},
+        ExportTraceServiceRequest, ExportTraceServiceResponse,
+    };
+    use tonic::{Request, Response, Status};
+
+    use std::sync::mpsc;
+
+    use super::*;
+
+    /// In-process OTLP/gRPC receiver forwarding every export request to the test
+    struct GrpcCollector(Mutex<mpsc::Sender<ExportTraceServiceRequest>>);
+
+    #[tonic::async_trait]
+    impl TraceService for GrpcCollector {
+        async fn export(
+            &self,
+            request: Request<ExportTraceServiceRequest>,
+        ) -> Result<Response<ExportTraceServiceResponse>, Status> {
+            let _ = self.0.lock().unwrap().send(request.into_inner());
+            Ok(Response::new(ExportTraceServiceResponse {
+                partial_success: None,
+            }))
+        }
+    }
+
+    #[test]
+    fn exports_spans_with_grpc() {
+        let _lock = EXPORT_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+
+        let listener = runtime
+            .block_on(tokio::net::TcpListener::bind("127.0.0.1:0"))
+            .unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let (sender, requests) = mpsc::channel();
+
+        runtime.spawn(
+            tonic::transport::Server::builder()
+                .add_service(TraceServiceServer::new(GrpcCollector(Mutex::new(sender))))
+                .serve_with_incoming(tokio_stream::wrappers::TcpListenerStream::new(listener)),
+        );
+
+        // The tonic client must be created within a Tokio runtime
+        let _guard = runtime.enter();
+        export_span(
+            TelemetryConfig::new(url, "otlp-protocols".to_string())
+                .with_protocol(TelemetryProtocol::Grpc),
+        );
+
+        let request = requests.recv_timeout(Duration::from_secs(10)).unwrap();
+        assert_eq!(
+            request.resource_spans[0].scope_spans[0].spans[0].name,
+            SPAN_NAME
+        );
+    }
+}
+
+#[cfg(feature = "logs")]
+#[test]
+fn exports_logs_with_http_protobuf() {
+    let _lock = EXPORT_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    let (url, requests) = http_collector();
+
+    export_span(TelemetryConfig::new(url, "otlp-protocols".to_string()));
+
+    let request = receive(&requests, "/v1/logs");
+    assert_eq!(request.content_type, "application/x-protobuf");
+    for expected in ["hello", "environment", "country", "otlp-protocols"] {
+        assert!(request
+            .body
+            .windows(expected.len())
+            .any(|window| window == expected.as_bytes()));
+    }
+}
+
+#[cfg(feature = "metrics")]
+#[test]
+fn exports_metrics_with_http_protobuf() {
+    let _lock = EXPORT_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    let (url, requests) = http_collector();
+
+    let subscriber = try_configure_subscriber(
+        builder("otlp-protocols")
+            .with_country(Country::Common)
+            .with_env(Environment::Dev)
+            .with_version("1.2.3".to_string())
+            .with_telemetry(url, "otlp-protocols".to_string())
+            .build(),
+    )
+    .expect("Failed to configure subscriber");
+
+    opentelemetry::global::meter("otlp-protocols")
+        .u64_counter("otlp_protocols_requests")
+        .build()
+        .add(1, &[]);
+
+    // Shuts down the meter provider, flushing the pending metrics
+    drop(Uninstall::for_subscriber(&subscriber));
+
+    let request = receive(&requests, "/v1/metrics");
+    assert_eq!(request.content_type, "application/x-protobuf");
+    for expected in ["otlp_protocols_requests", "country", "1.2.3"] {
+        assert!(request
+            .body
+            .windows(expected.len())
+            .any(|window| window == expected.as_bytes()));
+    }
+}
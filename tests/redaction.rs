@@ -0,0 +1,146 @@
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+
+use prima_tracing::{
+    builder, try_configure_subscriber, Country, Environment, Error, RedactionConfig,
+    RedactionPattern,
+};
+use tracing_subscriber::fmt::MakeWriter;
+
+#[derive(Clone, Default)]
+struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+impl Buffer {
+    fn lines(&self) -> Vec<serde_json::Value> {
+        let contents = String::from_utf8(self.0.lock().unwrap().clone()).unwrap();
+        contents
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+}
+
+impl Write for Buffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a> MakeWriter<'a> for Buffer {
+    type Writer = Self;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self.clone()
+    }
+}
+
+#[test]
+fn json_logs_are_redacted() {
+    let buffer = Buffer::default();
+    let subscriber = try_configure_subscriber(
+        builder("redaction-test")
+            .with_country(Country::It)
+            .with_env(Environment::Production)
+            .with_telemetry(
+                "http://localhost:55681".to_string(),
+                "redaction-test".to_string(),
+            )
+            .with_writer(buffer.clone())
+            .with_redaction(
+                RedactionConfig::default()
+                    .with_denied_field("*password*")
+                    .with_pattern(RedactionPattern::Email)
+                    .with_country_patterns(Country::It)
+                    .with_hashing("secret"),
+            )
+            .build(),
+    )
+    .expect("Failed to configure subscriber");
+
+    tracing::subscriber::with_default(subscriber, || {
+        let _span = tracing::info_span!("signup", email = "mario@example.com").entered();
+        tracing::info!(
+            password = "hunter2",
+            fiscal_code = "RSSMRA85T10A562S",
+            attempts = 1,
+            "signed up mario@example.com"
+        );
+    });
+
+    let lines = buffer.lines();
+    let line = &lines[0];
+    let email = line["current_span"]["email"].as_str().unwrap();
+    assert!(email.starts_with("[REDACTED:"));
+    // The same value is hashed the same way, wherever it's found
+    assert_eq!(line["message"], format!("signed up {email}"));
+    assert!(line["password"]
+        .as_str()
+        .is_some_and(|password| password.starts_with("[REDACTED:") && password != email));
+    assert!(line["fiscal_code"]
+        .as_str()
+        .is_some_and(|code| code.starts_with("[REDACTED:")));
+    assert_eq!(line["attempts"], 1);
+    assert!(!line.to_string().contains("mario"));
+}
+
+#[test]
+fn redaction_is_kept_by_each_subscriber() {
+    let configure = |buffer: &Buffer, redaction: Option<RedactionConfig>| {
+        let builder = builder("redaction-test")
+            .with_country(Country::It)
+            .with_env(Environment::Production)
+            .with_telemetry(
+                "http://localhost:55681".to_string(),
+                "redaction-test".to_string(),
+            )
+            .with_writer(buffer.clone());
+        let builder = match redaction {
+            Some(redaction) => builder.with_redaction(redaction),
+            None => builder,
+        };
+        try_configure_subscriber(builder.build()).expect("Failed to configure subscriber")
+    };
+
+    let redacted = Buffer::default();
+    let redacted_subscriber = configure(
+        &redacted,
+        Some(RedactionConfig::default().with_denied_field("password")),
+    );
+    // Configuring another subscriber afterwards doesn't change the redaction of the first one
+    let plain = Buffer::default();
+    let plain_subscriber = configure(&plain, None);
+
+    tracing::subscriber::with_default(redacted_subscriber, || {
+        tracing::info!(password = "hunter2", "signed up");
+    });
+    tracing::subscriber::with_default(plain_subscriber, || {
+        tracing::info!(password = "hunter2", "signed up");
+    });
+
+    assert_eq!(redacted.lines()[0]["password"], "[REDACTED]");
+    assert_eq!(plain.lines()[0]["password"], "hunter2");
+}
+
+#[test]
+fn invalid_redaction_pattern_is_reported() {
+    let result = try_configure_subscriber(
+        builder("redaction-test")
+            .with_country(Country::It)
+            .with_env(Environment::Production)
+            .with_telemetry(
+                "http://localhost:55681".to_string(),
+                "redaction-test".to_string(),
+            )
+            .with_redaction(RedactionConfig::default().with_custom_pattern("[a-"))
+            .build(),
+    );
+
+    assert!(matches!(
+        result.err(),
+        Some(Error::InvalidRedactionPattern { pattern, .. }) if pattern == "[a-"
+    ));
+}
@@ -0,0 +1,133 @@
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use prima_tracing::{
+    builder, try_configure_subscriber, Country, Environment, NonBlockingConfig, RollingFileConfig,
+    Rotation, Uninstall,
+};
+use tracing_subscriber::fmt::MakeWriter;
+
+#[derive(Clone, Default)]
+struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+impl Buffer {
+    fn contents(&self) -> String {
+        String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+    }
+}
+
+impl Write for Buffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a> MakeWriter<'a> for Buffer {
+    type Writer = Self;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self.clone()
+    }
+}
+
+#[test]
+fn logs_are_written_to_the_configured_writer() {
+    let buffer = Buffer::default();
+    let subscriber = try_configure_subscriber(
+        builder("writer-test")
+            .with_country(Country::Common)
+            .with_env(Environment::Dev)
+            .with_telemetry(
+                "http://localhost:55681".to_string(),
+                "writer-test".to_string(),
+            )
+            .with_writer(buffer.clone())
+            .with_pretty_output(false)
+            .build(),
+    )
+    .expect("Failed to configure subscriber");
+
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::info!("written to the buffer");
+    });
+
+    let contents = buffer.contents();
+    assert!(contents.contains("written to the buffer"));
+    #[cfg(feature = "json-logger")]
+    {
+        let line: serde_json::Value = serde_json::from_str(contents.trim()).unwrap();
+        assert_eq!(line["type"], "writer-test");
+        assert_eq!(line["message"], "written to the buffer");
+    }
+}
+
+#[test]
+fn non_blocking_logs_are_written_when_uninstalling() {
+    let buffer = Buffer::default();
+    let subscriber = try_configure_subscriber(
+        builder("writer-test")
+            .with_country(Country::Common)
+            .with_env(Environment::Dev)
+            .with_telemetry(
+                "http://localhost:55681".to_string(),
+                "writer-test".to_string(),
+            )
+            .with_writer(buffer.clone())
+            .with_non_blocking(NonBlockingConfig::default())
+            .build(),
+    )
+    .expect("Failed to configure subscriber");
+    let uninstall = Uninstall::for_subscriber(&subscriber);
+
+    tracing::subscriber::with_default(subscriber, || {
+        for line in 0..100 {
+            tracing::info!("non-blocking line {line}");
+        }
+    });
+
+    let writer = uninstall
+        .non_blocking_writer()
+        .expect("Missing non-blocking writer");
+    uninstall
+        .shutdown(Duration::from_secs(5))
+        .expect("Failed to shutdown");
+
+    assert_eq!(writer.dropped_lines(), 0);
+    assert_eq!(buffer.contents().lines().count(), 100);
+    assert!(buffer.contents().contains("non-blocking line 99"));
+}
+
+#[test]
+fn logs_are_written_to_the_file_alongside_the_writer() {
+    let buffer = Buffer::default();
+    let path = std::env::temp_dir()
+        .join(format!("prima-tracing-{}", uuid::Uuid::new_v4()))
+        .join("writer-test.log");
+    let subscriber = try_configure_subscriber(
+        builder("writer-test")
+            .with_country(Country::Common)
+            .with_env(Environment::Dev)
+            .with_telemetry(
+                "http://localhost:55681".to_string(),
+                "writer-test".to_string(),
+            )
+            .with_writer(buffer.clone())
+            .with_file_output(RollingFileConfig::new(&path, Rotation::Daily).with_stdout())
+            .build(),
+    )
+    .expect("Failed to configure subscriber");
+
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::info!("written to the file");
+    });
+
+    let file = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    assert!(file.contains("written to the file"));
+    assert_eq!(file, buffer.contents());
+}